@@ -1,7 +1,18 @@
-#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_clip}
+#import bevy_pbr::mesh_functions::{
+    get_world_from_local, mesh_position_local_to_clip, mesh_position_local_to_world,
+    mesh_normal_local_to_world,
+}
+#import bevy_pbr::mesh_view_bindings::view
+#ifdef GIZMO_XRAY
+#import bevy_pbr::prepass_utils::prepass_depth
+#endif
 
 struct GizmoMaterial {
     color: vec4<f32>,
+    line_width: f32,
+    highlight: f32,
+    shading: f32,
+    fade_axis: vec3<f32>,
 };
 
 @group(#{MATERIAL_BIND_GROUP}) @binding(0)
@@ -10,23 +21,150 @@ var<uniform> material: GizmoMaterial;
 struct Vertex {
     @builtin(instance_index) instance_index: u32,
     @location(0) position: vec3<f32>,
+#ifdef GIZMO_LINE
+    // x is the side of the line, y is 0 at the segment start and 1 at its end
+    @location(1) uv: vec2<f32>,
+    @location(2) other: vec3<f32>,
+#else
+#ifdef VERTEX_NORMALS
+    @location(1) normal: vec3<f32>,
+#endif
+#endif
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+    // opacity left after fading a handle that points at the camera
+    @location(0) fade: f32,
+#ifdef GIZMO_LINE
+    // signed distance in pixels from the center of the line
+    @location(1) line_distance: f32,
+#else
+    @location(1) world_position: vec3<f32>,
+#ifdef VERTEX_NORMALS
+    @location(2) world_normal: vec3<f32>,
+#endif
+#endif
 };
 
+// 1 unless the handle axis points almost straight at the camera.
+fn axis_fade(world_from_local: mat4x4<f32>) -> f32 {
+    if dot(material.fade_axis, material.fade_axis) == 0.0 {
+        return 1.0;
+    }
+    let world_axis = normalize((world_from_local * vec4<f32>(material.fade_axis, 0.0)).xyz);
+    let to_camera = normalize(view.world_position - world_from_local[3].xyz);
+    return 1.0 - smoothstep(0.9, 0.98, abs(dot(world_axis, to_camera)));
+}
+
+#ifdef GIZMO_LINE
+// Move `clip` along the segment towards `other` until it is in front of the camera.
+fn clip_near(clip: vec4<f32>, other: vec4<f32>) -> vec4<f32> {
+    let near = 0.0001;
+    if clip.w >= near || other.w < near {
+        return clip;
+    }
+    let t = (near - clip.w) / (other.w - clip.w);
+    return mix(clip, other, t);
+}
+
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
     var out: VertexOutput;
+    let world_from_local = get_world_from_local(vertex.instance_index);
+    let this_clip_unclipped = mesh_position_local_to_clip(world_from_local, vec4<f32>(vertex.position, 1.0));
+    let other_clip_unclipped = mesh_position_local_to_clip(world_from_local, vec4<f32>(vertex.other, 1.0));
+    let this_clip = clip_near(this_clip_unclipped, other_clip_unclipped);
+    let other_clip = clip_near(other_clip_unclipped, this_clip_unclipped);
+
+    let resolution = view.viewport.zw;
+    let this_screen = this_clip.xy / this_clip.w * resolution * 0.5;
+    let other_screen = other_clip.xy / other_clip.w * resolution * 0.5;
+
+    // both ends of a segment must agree on the direction so the sides match up
+    var direction = other_screen - this_screen;
+    if vertex.uv.y > 0.5 {
+        direction = -direction;
+    }
+    if dot(direction, direction) < 1e-8 {
+        direction = vec2<f32>(1.0, 0.0);
+    }
+    direction = normalize(direction);
+    let normal = vec2<f32>(-direction.y, direction.x);
+
+    // one extra pixel on each side for the anti-aliased fringe
+    let half_width = material.line_width * 0.5 + 1.0;
+    let offset = normal * vertex.uv.x * half_width;
+
+    out.clip_position = this_clip;
+    out.clip_position.x += offset.x / resolution.x * 2.0 * this_clip.w;
+    out.clip_position.y += offset.y / resolution.y * 2.0 * this_clip.w;
+    out.line_distance = vertex.uv.x * half_width;
+    out.fade = axis_fade(world_from_local);
+    return out;
+}
+#else
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_from_local = get_world_from_local(vertex.instance_index);
     out.clip_position = mesh_position_local_to_clip(
-        get_world_from_local(vertex.instance_index),
+        world_from_local,
         vec4<f32>(vertex.position, 1.0),
     );
+    out.world_position = mesh_position_local_to_world(
+        world_from_local,
+        vec4<f32>(vertex.position, 1.0),
+    ).xyz;
+#ifdef VERTEX_NORMALS
+    out.world_normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+#endif
+    out.fade = axis_fade(world_from_local);
     return out;
 }
+#endif
+
+#ifdef GIZMO_XRAY
+// Opacity of the fragment once compared to the scene depth: hidden parts are dimmed and dashed.
+fn x_ray_alpha(frag_coord: vec4<f32>, sample_index: u32) -> f32 {
+#ifdef DEPTH_PREPASS
+    let scene_depth = prepass_depth(frag_coord, sample_index);
+    // reversed z, a smaller depth is further away
+    if frag_coord.z < scene_depth {
+        if fract((frag_coord.x + frag_coord.y) / 12.0) > 0.5 {
+            discard;
+        }
+        return 0.35;
+    }
+#endif
+    return 1.0;
+}
+#endif
 
 @fragment
-fn fragment() -> @location(0) vec4<f32> {
-    return material.color;
+fn fragment(
+    in: VertexOutput,
+#ifdef MULTISAMPLED
+    @builtin(sample_index) sample_index: u32,
+#endif
+) -> @location(0) vec4<f32> {
+#ifndef MULTISAMPLED
+    let sample_index = 0u;
+#endif
+    var color = material.color;
+#ifdef GIZMO_LINE
+    color.a *= clamp(material.line_width * 0.5 + 0.5 - abs(in.line_distance), 0.0, 1.0);
+#else
+#ifdef VERTEX_NORMALS
+    // headlight shading, both sides lit since the gizmo is drawn without culling
+    let to_camera = normalize(view.world_position - in.world_position);
+    let facing = abs(dot(normalize(in.world_normal), to_camera));
+    color = vec4<f32>(color.rgb * mix(1.0, 0.45 + 0.55 * facing, material.shading), color.a);
+#endif
+#endif
+    color = vec4<f32>(mix(color.rgb, vec3<f32>(1.0), material.highlight), color.a * in.fade);
+#ifdef GIZMO_XRAY
+    color.a *= x_ray_alpha(in.clip_position, sample_index);
+#endif
+    return color;
 }
@@ -0,0 +1,126 @@
+#import bevy_pbr::mesh_functions::{get_world_from_local, mesh_position_local_to_world}
+#import bevy_pbr::view_transformations::position_world_to_clip
+#import bevy_pbr::mesh_view_bindings::view
+
+struct GridMaterial {
+    minor_color: vec4<f32>,
+    major_color: vec4<f32>,
+    x_axis_color: vec4<f32>,
+    y_axis_color: vec4<f32>,
+    z_axis_color: vec4<f32>,
+    spacing: f32,
+    major_every: f32,
+    fade_distance: f32,
+    plane: u32,
+};
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0)
+var<uniform> material: GridMaterial;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    let world_position = mesh_position_local_to_world(
+        get_world_from_local(vertex.instance_index),
+        vec4<f32>(vertex.position, 1.0),
+    );
+    out.world_position = world_position.xyz;
+    out.clip_position = position_world_to_clip(world_position.xyz);
+    return out;
+}
+
+// position in the grid plane
+fn plane_coords(p: vec3<f32>) -> vec2<f32> {
+    switch material.plane {
+        case 1u: { return p.xy; }
+        case 2u: { return p.yz; }
+        default: { return p.xz; }
+    }
+}
+
+// distance of a point off the grid plane
+fn plane_height(p: vec3<f32>) -> f32 {
+    switch material.plane {
+        case 1u: { return abs(p.z); }
+        case 2u: { return abs(p.x); }
+        default: { return abs(p.y); }
+    }
+}
+
+// colors of the lines along the first and second plane coordinate axes
+fn axis_colors() -> array<vec4<f32>, 2> {
+    switch material.plane {
+        case 1u: { return array(material.x_axis_color, material.y_axis_color); }
+        case 2u: { return array(material.y_axis_color, material.z_axis_color); }
+        default: { return array(material.x_axis_color, material.z_axis_color); }
+    }
+}
+
+// anti-aliased coverage of one pixel wide lines every `cell` units
+fn grid_coverage(coord: vec2<f32>, cell: f32) -> f32 {
+    let c = coord / cell;
+    let width = fwidth(c);
+    let dist = abs(fract(c - 0.5) - 0.5) / width;
+    return 1.0 - min(min(dist.x, dist.y), 1.0);
+}
+
+// anti-aliased coverage of a line along `coord == 0`
+fn axis_coverage(coord: f32) -> f32 {
+    return 1.0 - min(abs(coord) / (1.5 * fwidth(coord)), 1.0);
+}
+
+// straight alpha "over", the material blends with AlphaMode::Blend which multiplies by alpha
+fn over(dst: vec4<f32>, src: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let a = src.a * coverage;
+    let out_a = a + dst.a * (1.0 - a);
+    if out_a <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    let rgb = (src.rgb * a + dst.rgb * dst.a * (1.0 - a)) / out_a;
+    return vec4<f32>(rgb, out_a);
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coord = plane_coords(in.world_position);
+    let height = max(plane_height(view.world_position), material.spacing);
+
+    // Every time the camera gets `major_every` times further from the plane the cells grow by
+    // the same factor, blending between levels so lines fade in and out instead of popping.
+    let lod = max(log(height / material.spacing) / log(material.major_every), 0.0);
+    let blend = fract(lod);
+    let cell = material.spacing * pow(material.major_every, floor(lod));
+
+    var color = vec4<f32>(0.0);
+    color = over(color, material.minor_color, grid_coverage(coord, cell) * (1.0 - blend));
+    let mid_color = mix(material.major_color, material.minor_color, blend);
+    color = over(color, mid_color, grid_coverage(coord, cell * material.major_every));
+    color = over(
+        color,
+        material.major_color,
+        grid_coverage(coord, cell * material.major_every * material.major_every),
+    );
+
+    let axes = axis_colors();
+    // the first coordinate axis is the line where the second coordinate is zero
+    color = over(color, axes[0], axis_coverage(coord.y));
+    color = over(color, axes[1], axis_coverage(coord.x));
+
+    let fade_end = max(material.fade_distance, height * 10.0);
+    let dist = distance(view.world_position, in.world_position);
+    color.a *= 1.0 - smoothstep(fade_end * 0.5, fade_end, dist);
+    if color.a <= 0.001 {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,232 @@
+use bevy::{camera::visibility::RenderLayers, light::NotShadowCaster, prelude::*};
+
+use crate::{axis::render_camera, grid_material::GridMaterial};
+
+/// Plane the infinite grid is drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum GridPlane {
+    #[default]
+    XZ,
+    XY,
+    YZ,
+}
+
+impl GridPlane {
+    pub fn normal(&self) -> Vec3 {
+        match self {
+            GridPlane::XZ => Vec3::Y,
+            GridPlane::XY => Vec3::Z,
+            GridPlane::YZ => Vec3::X,
+        }
+    }
+
+    fn shader_index(&self) -> u32 {
+        match self {
+            GridPlane::XZ => 0,
+            GridPlane::XY => 1,
+            GridPlane::YZ => 2,
+        }
+    }
+}
+
+/// Settings of the infinite ground grid.
+///
+/// `spacing` is also the translation snapping step of the gizmo, so a snapped drag puts the pivot
+/// of the selection on the smallest grid lines.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct GridSettings {
+    pub visible: bool,
+    pub plane: GridPlane,
+    /// Size of the smallest grid cell and the translation snapping step.
+    pub spacing: f32,
+    /// Number of minor cells in a major cell.
+    pub major_every: u32,
+    /// Distance from the camera at which the grid has faded out when close to the plane.
+    pub fade_distance: f32,
+    /// Snap gizmo translations to multiples of `spacing`.
+    pub snap: bool,
+    pub minor_color: Color,
+    pub major_color: Color,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            plane: GridPlane::XZ,
+            spacing: 0.1,
+            major_every: 10,
+            fade_distance: 30.0,
+            snap: false,
+            minor_color: Color::srgba(0.5, 0.5, 0.5, 0.3),
+            major_color: Color::srgba(0.6, 0.6, 0.6, 0.6),
+        }
+    }
+}
+
+impl GridSettings {
+    fn snapping(&self) -> bool {
+        self.snap && self.spacing > 0.0
+    }
+
+    /// Snap a drag of `distance` along the unit `axis` that starts at `origin`, so the position
+    /// it ends at lands on a multiple of the snapping step along that axis.
+    pub fn snap_distance(&self, origin: Vec3, axis: Vec3, distance: f32) -> f32 {
+        if !self.snapping() {
+            return distance;
+        }
+        let start = origin.dot(axis);
+        ((start + distance) / self.spacing).round() * self.spacing - start
+    }
+
+    /// Snap a `translation` that starts at `origin`, so the position it ends at lands on the
+    /// closest grid point. With a `plane_normal` the correction is flattened onto that plane so
+    /// the drag stays in it.
+    pub fn snap_translation(
+        &self,
+        origin: Vec3,
+        translation: Vec3,
+        plane_normal: Option<Vec3>,
+    ) -> Vec3 {
+        if !self.snapping() {
+            return translation;
+        }
+        let position = origin + translation;
+        let mut correction = (position / self.spacing).round() * self.spacing - position;
+        if let Some(normal) = plane_normal.and_then(Vec3::try_normalize) {
+            correction -= normal * correction.dot(normal);
+        }
+        translation + correction
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct GridMarker;
+
+pub(crate) fn spawn_grid(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+    settings: Res<GridSettings>,
+) {
+    commands.spawn((
+        Name::from("Grid"),
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(1.0, 1.0))),
+        MeshMaterial3d(materials.add(grid_material(&settings))),
+        Transform::default(),
+        visibility(&settings),
+        NotShadowCaster,
+        Pickable::IGNORE,
+        GridMarker,
+    ));
+}
+
+fn grid_material(settings: &GridSettings) -> GridMaterial {
+    GridMaterial {
+        minor_color: settings.minor_color.into(),
+        major_color: settings.major_color.into(),
+        x_axis_color: Color::srgb(1.0, 0.0, 0.0).into(),
+        y_axis_color: Color::srgb(0.0, 1.0, 0.0).into(),
+        z_axis_color: Color::srgb(0.0, 0.0, 1.0).into(),
+        spacing: settings.spacing.max(f32::EPSILON),
+        major_every: settings.major_every.max(2) as f32,
+        fade_distance: settings.fade_distance,
+        plane: settings.plane.shader_index(),
+    }
+}
+
+fn visibility(settings: &GridSettings) -> Visibility {
+    if settings.visible {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    }
+}
+
+/// Push changed [`GridSettings`] into the grid material.
+pub(crate) fn update_grid_settings(
+    settings: Res<GridSettings>,
+    mut grid: Query<(&MeshMaterial3d<GridMaterial>, &mut Visibility), With<GridMarker>>,
+    mut materials: ResMut<Assets<GridMaterial>>,
+) {
+    for (material, mut visibility) in grid.iter_mut() {
+        if let Some(grid_material) = materials.get_mut(material) {
+            *grid_material = self::grid_material(&settings);
+        }
+        *visibility = self::visibility(&settings);
+    }
+}
+
+/// Keep the grid quad under the camera and large enough to reach where the grid fades out.
+pub(crate) fn follow_camera(
+    settings: Res<GridSettings>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, Option<&RenderLayers>)>,
+    mut grid: Query<(&mut Transform, Option<&RenderLayers>), With<GridMarker>>,
+) {
+    for (mut transform, grid_layers) in grid.iter_mut() {
+        let grid_layers = grid_layers.cloned().unwrap_or_default();
+        let Some((_, _, camera_transform)) = render_camera(&grid_layers, cameras.iter()) else {
+            continue;
+        };
+
+        let normal = settings.plane.normal();
+        let camera_position = camera_transform.translation();
+        let height = camera_position.dot(normal).abs();
+        // matches fade_end in grid_material.wgsl
+        let fade_end = settings
+            .fade_distance
+            .max(height.max(settings.spacing) * 10.0);
+
+        transform.translation = camera_position - normal * camera_position.dot(normal);
+        transform.rotation = Quat::from_rotation_arc(Vec3::Y, normal);
+        transform.scale = Vec3::splat(fade_end * 2.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapping() -> GridSettings {
+        GridSettings {
+            spacing: 0.5,
+            snap: true,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn snapped_distance_lands_on_the_grid() {
+        let origin = Vec3::new(0.2, 0.0, 0.0);
+        let distance = snapping().snap_distance(origin, Vec3::X, 0.9);
+        assert!((origin.x + distance - 1.0).abs() < 1e-5);
+        // off the axis, only the position along the axis is snapped
+        let origin = Vec3::new(0.2, 0.3, 0.0);
+        let distance = snapping().snap_distance(origin, Vec3::Y, -0.1);
+        assert!((origin.y + distance - 0.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn snapped_translation_lands_on_the_grid() {
+        let origin = Vec3::new(0.2, 0.1, -0.3);
+        let translation = snapping().snap_translation(origin, Vec3::new(0.9, 0.0, 0.0), None);
+        assert!((origin + translation).abs_diff_eq(Vec3::new(1.0, 0.0, -0.5), 1e-5));
+    }
+
+    #[test]
+    fn snapped_translation_stays_in_its_plane() {
+        let origin = Vec3::new(0.2, 0.1, -0.3);
+        let translation =
+            snapping().snap_translation(origin, Vec3::new(0.9, 0.0, 0.4), Some(Vec3::Y));
+        assert!((origin + translation).abs_diff_eq(Vec3::new(1.0, 0.1, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn no_snapping_keeps_the_drag() {
+        let grid = GridSettings::default();
+        assert_eq!(grid.snap_distance(Vec3::splat(0.23), Vec3::X, 0.77), 0.77);
+        let translation = grid.snap_translation(Vec3::ONE, Vec3::splat(0.3), None);
+        assert_eq!(translation, Vec3::splat(0.3));
+    }
+}
@@ -0,0 +1,114 @@
+use bevy::{camera::visibility::RenderLayers, prelude::*};
+
+use crate::axis::{AxisLength, AxisMarker, AxisSettings, render_camera};
+
+/// Screen-space text anchored to a point on an axis, kept facing the camera by drawing it in the UI.
+#[derive(Component)]
+pub(crate) struct AxisLabel {
+    direction: Vec3,
+    /// Distance from the origin, `None` follows the camera distance for infinite axes.
+    distance: Option<f32>,
+}
+
+/// Despawn the axis labels and spawn them again from the current [`AxisSettings`].
+pub(crate) fn spawn_axis_labels(
+    mut commands: Commands,
+    settings: Res<AxisSettings>,
+    existing: Query<Entity, With<AxisLabel>>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, Option<&RenderLayers>)>,
+) {
+    for entity in existing.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // without a target the UI goes to the highest order camera, which may be a corner viewport
+    let target = render_camera(&settings.render_layers, cameras.iter()).map(|(e, _, _)| e);
+
+    let axes = [
+        ("X", settings.show_x, Vec3::X, settings.x_color),
+        ("Y", settings.show_y, Vec3::Y, settings.y_color),
+        ("Z", settings.show_z, Vec3::Z, settings.z_color),
+    ];
+    let decimals = settings
+        .tick_spacing
+        .map(|spacing| (-spacing.log10()).ceil().max(0.0) as usize)
+        .unwrap_or_default();
+
+    let mut labels = Vec::new();
+    for (name, show, axis, color) in axes {
+        if !show {
+            continue;
+        }
+        if settings.labels {
+            let distance = match settings.length {
+                AxisLength::Finite(length) => Some(length),
+                AxisLength::Infinite => None,
+            };
+            labels.push((name.to_string(), axis, distance, color));
+        }
+        let mut signs = vec![(1.0, color)];
+        if settings.show_negative {
+            signs.push((-1.0, settings.negative_color));
+        }
+        for (sign, color) in signs {
+            for distance in settings.tick_distances() {
+                let text = format!("{:.*}", decimals, sign * distance);
+                labels.push((text, axis * sign, Some(distance), color));
+            }
+        }
+    }
+
+    for (text, direction, distance, color) in labels {
+        let mut label = commands.spawn((
+            Text::new(text),
+            TextFont {
+                font_size: settings.label_font_size,
+                ..default()
+            },
+            TextColor(color),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Visibility::Hidden,
+            Pickable::IGNORE,
+            AxisLabel {
+                direction,
+                distance,
+            },
+        ));
+        if let Some(target) = target {
+            label.insert(UiTargetCamera(target));
+        }
+    }
+}
+
+/// Move the labels to where their anchor points are on screen.
+pub(crate) fn position_axis_labels(
+    settings: Res<AxisSettings>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, Option<&RenderLayers>)>,
+    axes: Query<&Visibility, (With<AxisMarker>, Without<AxisLabel>)>,
+    mut labels: Query<(&AxisLabel, &mut Node, &mut Visibility)>,
+) {
+    let axes_hidden = axes.iter().all(|vis| *vis == Visibility::Hidden);
+    let camera = render_camera(&settings.render_layers, cameras.iter());
+
+    for (label, mut node, mut visibility) in labels.iter_mut() {
+        let Some((_, camera, camera_transform)) = camera.filter(|_| !axes_hidden) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        let distance = label
+            .distance
+            .unwrap_or_else(|| camera_transform.translation().length() * 0.75);
+        let Ok(position) = camera.world_to_viewport(camera_transform, label.direction * distance)
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        // offset a little so the text doesn't sit on top of the line
+        node.left = Val::Px(position.x + 4.0);
+        node.top = Val::Px(position.y + 2.0);
+        *visibility = Visibility::Inherited;
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::{
+    camera::visibility::RenderLayers,
+    light::NotShadowCaster,
+    prelude::*,
+    window::{PrimaryWindow, WindowScaleFactorChanged},
+};
+
+use crate::{
+    axis::{
+        grid::{GridSettings, follow_camera, spawn_grid, update_grid_settings},
+        labels::{position_axis_labels, spawn_axis_labels},
+    },
+    gizmo_material::GizmoMaterial,
+    grid_material::GridMaterial,
+    mesh::line::{LineList, LineStrip},
+};
+
+pub mod grid;
+mod labels;
+
+/// Length used for the axis lines in [`AxisLength::Infinite`] mode.
+const INFINITE_AXIS_LENGTH: f32 = 10_000.0;
+/// Upper bound on the ticks drawn on each half axis, mostly for [`AxisLength::Infinite`].
+const MAX_TICKS_PER_HALF_AXIS: usize = 100;
+
+pub struct AxisPlugin;
+
+impl Plugin for AxisPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridSettings>()
+            .init_resource::<AxisSettings>()
+            .register_type::<GridSettings>()
+            .register_type::<AxisSettings>()
+            .add_plugins(MaterialPlugin::<GridMaterial>::default())
+            .add_systems(Startup, spawn_grid)
+            .add_systems(
+                Update,
+                (
+                    keyboard_axis,
+                    // the line width is in logical pixels, the material wants physical ones
+                    spawn_main_axis.run_if(
+                        resource_changed::<AxisSettings>.or(on_message::<WindowScaleFactorChanged>),
+                    ),
+                    spawn_axis_labels.run_if(resource_changed::<AxisSettings>),
+                    position_axis_labels.after(spawn_axis_labels),
+                    update_grid_settings.run_if(resource_changed::<GridSettings>),
+                    follow_camera,
+                ),
+            );
+    }
+}
+
+/// How far the axis lines reach from the origin.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum AxisLength {
+    Finite(f32),
+    Infinite,
+}
+
+impl AxisLength {
+    fn value(&self) -> f32 {
+        match self {
+            AxisLength::Finite(length) => *length,
+            AxisLength::Infinite => INFINITE_AXIS_LENGTH,
+        }
+    }
+}
+
+/// Settings of the world axis lines, changing them at runtime respawns the axes.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct AxisSettings {
+    pub show_x: bool,
+    pub show_y: bool,
+    pub show_z: bool,
+    /// Draw the negative half of each shown axis in `negative_color`.
+    pub show_negative: bool,
+    pub length: AxisLength,
+    /// Width of the lines in logical pixels.
+    pub line_width: f32,
+    pub x_color: Color,
+    pub y_color: Color,
+    pub z_color: Color,
+    pub negative_color: Color,
+    /// Key toggling the visibility of all the axes.
+    pub toggle_key: Option<KeyCode>,
+    pub render_layers: RenderLayers,
+    /// Show "X", "Y" and "Z" at the positive ends of the axes.
+    pub labels: bool,
+    /// Distance between ruler ticks and their numeric labels, `None` hides the ticks.
+    pub tick_spacing: Option<f32>,
+    pub label_font_size: f32,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            show_x: true,
+            show_y: true,
+            show_z: true,
+            show_negative: true,
+            length: AxisLength::Finite(10.0),
+            line_width: 2.0,
+            x_color: Color::srgb(1.0, 0.0, 0.0),
+            y_color: Color::srgb(0.0, 1.0, 0.0),
+            z_color: Color::srgb(0.0, 0.0, 1.0),
+            negative_color: Color::srgb(0.4, 0.4, 0.4),
+            toggle_key: Some(KeyCode::KeyA),
+            render_layers: RenderLayers::layer(0),
+            labels: true,
+            tick_spacing: None,
+            label_font_size: 14.0,
+        }
+    }
+}
+
+impl AxisSettings {
+    /// Positions of the ruler ticks along one half axis, excluding the origin.
+    fn tick_distances(&self) -> Vec<f32> {
+        let Some(spacing) = self.tick_spacing.filter(|spacing| *spacing > 0.0) else {
+            return Vec::new();
+        };
+        let count = ((self.length.value() / spacing) as usize).min(MAX_TICKS_PER_HALF_AXIS);
+        (1..=count).map(|i| i as f32 * spacing).collect()
+    }
+}
+
+/// Lowest order active camera that renders something on `layers`.
+pub(crate) fn render_camera<'a>(
+    layers: &RenderLayers,
+    cameras: impl Iterator<
+        Item = (
+            Entity,
+            &'a Camera,
+            &'a GlobalTransform,
+            Option<&'a RenderLayers>,
+        ),
+    >,
+) -> Option<(Entity, &'a Camera, &'a GlobalTransform)> {
+    cameras
+        .filter(|(_, camera, _, camera_layers)| {
+            camera.is_active && layers.intersects(camera_layers.unwrap_or_default())
+        })
+        .min_by_key(|(_, camera, _, _)| camera.order)
+        .map(|(entity, camera, transform, _)| (entity, camera, transform))
+}
+
+fn keyboard_axis(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<AxisSettings>,
+    query: Query<&mut Visibility, With<AxisMarker>>,
+) {
+    let Some(toggle_key) = settings.toggle_key else {
+        return;
+    };
+    if keyboard.just_pressed(toggle_key) {
+        for mut vis in query {
+            *vis = match *vis {
+                Visibility::Inherited => Visibility::Inherited,
+                Visibility::Hidden => Visibility::Visible,
+                Visibility::Visible => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+#[derive(Component)]
+struct AxisMarker;
+
+/// Despawn the axes and spawn them again from the current [`AxisSettings`].
+fn spawn_main_axis(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+    settings: Res<AxisSettings>,
+    existing: Query<(Entity, &Visibility), With<AxisMarker>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    // keep the toggled visibility across respawns
+    let mut visibility = Visibility::Visible;
+    for (entity, vis) in existing.iter() {
+        visibility = *vis;
+        commands.entity(entity).despawn();
+    }
+
+    let length = settings.length.value();
+    let scale_factor = windows.single().map_or(1.0, Window::scale_factor);
+    let line_width = settings.line_width * scale_factor;
+    let negative = materials.add(GizmoMaterial::line(settings.negative_color, line_width));
+
+    let axes = [
+        ("x", settings.show_x, Vec3::X, settings.x_color),
+        ("y", settings.show_y, Vec3::Y, settings.y_color),
+        ("z", settings.show_z, Vec3::Z, settings.z_color),
+    ];
+
+    let empty: Entity = commands
+        .spawn((
+            Transform::from_translation(Vec3::ZERO),
+            visibility,
+            InheritedVisibility::default(),
+            Name::from("Main Axis"),
+            AxisMarker,
+        ))
+        .id();
+
+    commands.entity(empty).with_children(|parent| {
+        for (name, show, axis, color) in axes {
+            if !show {
+                continue;
+            }
+            let positive = materials.add(GizmoMaterial::line(color, line_width));
+            let mut halves = vec![(1.0, format!("{name}-axis"), positive)];
+            if settings.show_negative {
+                halves.push((-1.0, format!("negative {name}-axis"), negative.clone()));
+            }
+            for (sign, name, material) in halves {
+                let direction = axis * sign;
+                parent.spawn((
+                    Name::from(name),
+                    Mesh3d(meshes.add(LineStrip::segment(Vec3::ZERO, direction * length))),
+                    MeshMaterial3d(material.clone()),
+                    NotShadowCaster,
+                    Pickable::IGNORE,
+                    settings.render_layers.clone(),
+                ));
+
+                // ruler ticks cross the axis along the next axis over
+                let tick_direction = axis.zxy();
+                let tick_half_length = settings.tick_spacing.unwrap_or_default() * 0.1;
+                let ticks = LineList {
+                    segments: settings
+                        .tick_distances()
+                        .into_iter()
+                        .map(|distance| {
+                            let center = direction * distance;
+                            let offset = tick_direction * tick_half_length;
+                            (center - offset, center + offset)
+                        })
+                        .collect(),
+                };
+                if !ticks.segments.is_empty() {
+                    parent.spawn((
+                        Name::from("axis ticks"),
+                        Mesh3d(meshes.add(ticks)),
+                        MeshMaterial3d(material),
+                        NotShadowCaster,
+                        Pickable::IGNORE,
+                        settings.render_layers.clone(),
+                    ));
+                }
+            }
+        }
+    });
+}
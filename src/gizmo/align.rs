@@ -0,0 +1,107 @@
+use bevy::{
+    picking::{hover::HoverMap, pointer::PointerId},
+    prelude::*,
+};
+
+use crate::gizmo::{
+    GizmoSettings, PickSelection, TransformGizmo,
+    drag_math::{align_axis, align_axis_keep_yaw, write_finite},
+    history::{TransformEdit, TransformHistory},
+    surface::SceneFilter,
+};
+
+/// Turn every selected entity so its local `axis` points along the normal of the scene surface
+/// under the mouse.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AlignToSurface {
+    pub axis: Vec3,
+    /// Keep only the heading around world Y and tilt from there, instead of turning the
+    /// current orientation the shortest way.
+    pub keep_yaw: bool,
+}
+
+impl Default for AlignToSurface {
+    fn default() -> Self {
+        Self {
+            axis: Vec3::Y,
+            keep_yaw: false,
+        }
+    }
+}
+
+/// Trigger [`AlignToSurface`] with [`GizmoSettings::align_key`].
+pub(crate) fn align_key(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<GizmoSettings>,
+) {
+    if settings
+        .align_key
+        .is_some_and(|key| keyboard.just_pressed(key))
+    {
+        commands.trigger(AlignToSurface::default());
+    }
+}
+
+pub(crate) fn align_to_surface(
+    align: On<AlignToSurface>,
+    hover_map: Res<HoverMap>,
+    scene: SceneFilter,
+    gizmo: Query<&TransformGizmo>,
+    mut items: Query<
+        (Entity, &mut Transform, &PickSelection, Option<&ChildOf>),
+        Without<TransformGizmo>,
+    >,
+    spaces: Query<&GlobalTransform>,
+    mut history: ResMut<TransformHistory>,
+) {
+    if gizmo
+        .iter()
+        .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        warn!("not aligning during a drag");
+        return;
+    }
+    let normal = hover_map
+        .get(&PointerId::Mouse)
+        .into_iter()
+        .flatten()
+        .filter(|(entity, _)| scene.is_scene(**entity))
+        .filter_map(|(_, hit)| Some((hit.depth, hit.normal?)))
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, normal)| normal);
+    let Some(normal) = normal else {
+        warn!("no surface under the mouse to align to");
+        return;
+    };
+
+    let mut edit = TransformEdit::new("align to surface");
+    let selected = items.iter_mut().filter(|(_, _, pick, _)| pick.is_selected);
+    for (entity, mut transform, _, parent) in selected {
+        // the normal is in world space, the rotation of a child in the space of its parent
+        let parent_rotation = parent
+            .and_then(|parent| spaces.get(parent.parent()).ok())
+            .map_or(Quat::IDENTITY, GlobalTransform::rotation);
+        let world_rotation = parent_rotation * transform.rotation;
+        let rotation = match align.keep_yaw {
+            true => align_axis_keep_yaw(world_rotation, align.axis, normal),
+            false => align_axis(world_rotation, align.axis, normal),
+        };
+        let Some(rotation) = rotation.map(|rotation| parent_rotation.inverse() * rotation) else {
+            warn!(
+                "can't align axis {:?} of {entity} to normal {:?}",
+                align.axis, normal
+            );
+            continue;
+        };
+        let aligned = Transform {
+            rotation,
+            ..*transform
+        };
+        let before = *transform;
+        if write_finite(&mut transform, aligned) {
+            edit.push(entity, before, aligned);
+        }
+    }
+    history.record(edit);
+}
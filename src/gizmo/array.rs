@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+
+use crate::gizmo::{
+    GizmoHandleClicked, GizmoPickSource, GizmoSettings, PickSelection, TransformGizmo,
+    TransformGizmoInteraction,
+};
+
+/// How the copies of an array are laid out, along or around an axis of the source in its own
+/// space.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum ArrayKind {
+    /// In a row along `axis`, [`ArrayTool::spacing`] apart.
+    Linear { axis: Vec3 },
+    /// In a circle around `axis`, [`ArrayTool::angle`] apart.
+    Radial { axis: Vec3 },
+}
+
+/// Array being edited, its copies are kept in sync with these values until [`ConfirmArray`]
+/// keeps them or [`CancelArray`] removes them.
+///
+/// Started by clicking a translation or rotation axis handle while holding
+/// [`GizmoSettings::array_key`] with one entity selected. While editing, up and down change the
+/// count, left and right the spacing or the angle and with Shift held the radius, Enter confirms
+/// and Escape cancels.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct ArrayTool {
+    /// Entity that is repeated, `None` when no array is being edited.
+    pub source: Option<Entity>,
+    pub kind: ArrayKind,
+    /// Number of copies, not counting the source.
+    pub count: usize,
+    /// Distance between the copies of a linear array.
+    pub spacing: f32,
+    /// Angle in radians between the copies of a radial array.
+    pub angle: f32,
+    /// Distance from the source of a radial array to the axis it is repeated around.
+    pub radius: f32,
+    /// Direction from the source of a radial array to the axis it is repeated around, in the
+    /// space of the source. Only the part perpendicular to the axis counts. Starting a radial
+    /// array sets it to the next local axis after the clicked one, Y for X, Z for Y and X for Z.
+    pub center_direction: Vec3,
+    #[reflect(ignore)]
+    copies: Vec<Entity>,
+}
+
+impl Default for ArrayTool {
+    fn default() -> Self {
+        Self {
+            source: None,
+            kind: ArrayKind::Linear { axis: Vec3::X },
+            count: 3,
+            spacing: 1.5,
+            angle: std::f32::consts::TAU / 4.0,
+            radius: 2.0,
+            center_direction: Vec3::Z,
+            copies: Vec::new(),
+        }
+    }
+}
+
+/// Change of the spacing or the radius for one press of an arrow key.
+const ARRAY_DISTANCE_STEP: f32 = 0.1;
+
+impl ArrayTool {
+    /// Change the spacing of a linear array, or the angle of a radial one, by `step` key
+    /// presses. With `radius` the radius of a radial array changes instead. The distances go
+    /// through zero to the other side.
+    fn nudge(&mut self, step: f32, radius: bool) {
+        match (self.kind, radius) {
+            (ArrayKind::Linear { .. }, _) => self.spacing += ARRAY_DISTANCE_STEP * step,
+            (ArrayKind::Radial { .. }, false) => self.angle += 5f32.to_radians() * step,
+            (ArrayKind::Radial { .. }, true) => self.radius += ARRAY_DISTANCE_STEP * step,
+        }
+    }
+
+    /// Transform of copy number `index`, counting from 1, for a source at `source`.
+    fn copy_transform(&self, source: &Transform, index: usize) -> Transform {
+        let steps = index as f32;
+        match self.kind {
+            ArrayKind::Linear { axis } => Transform {
+                translation: source.translation + source.rotation * axis * self.spacing * steps,
+                ..*source
+            },
+            ArrayKind::Radial { axis } => {
+                let world_axis = source.rotation * axis;
+                let direction = self.center_direction.reject_from(axis).normalize_or_zero();
+                let center = source.translation + source.rotation * direction * self.radius;
+                let turn = Quat::from_axis_angle(world_axis.normalize(), self.angle * steps);
+                Transform {
+                    translation: center + turn * (source.translation - center),
+                    rotation: turn * source.rotation,
+                    ..*source
+                }
+            }
+        }
+    }
+}
+
+/// Keep the copies of the array being edited.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct ConfirmArray;
+
+/// Remove the copies of the array being edited.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct CancelArray;
+
+/// Text listing the count and spacing of the array being edited.
+#[derive(Component)]
+pub(crate) struct ArrayLabel;
+
+pub(crate) fn spawn_array_label(mut commands: Commands) {
+    commands.spawn((
+        Name::from("Array Label"),
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            top: Val::Px(8.0),
+            display: Display::None,
+            ..default()
+        },
+        Pickable::IGNORE,
+        ArrayLabel,
+    ));
+}
+
+/// Start an array along or around the clicked axis handle.
+pub(crate) fn start_array(
+    click: On<GizmoHandleClicked>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<GizmoSettings>,
+    gizmo: Query<&TransformGizmo>,
+    selection: Query<(Entity, &PickSelection)>,
+    mut tool: ResMut<ArrayTool>,
+) {
+    if !settings.array_key.is_some_and(|key| keyboard.pressed(key)) {
+        return;
+    }
+    // both take Enter and Escape
+    if gizmo.iter().any(|gizmo| gizmo.grabbing) {
+        warn!("put the grabbed selection down before starting an array");
+        return;
+    }
+    let kind = match click.interaction {
+        TransformGizmoInteraction::TranslateAxis { axis, .. } => ArrayKind::Linear { axis },
+        TransformGizmoInteraction::RotateAxis { axis, .. } => ArrayKind::Radial { axis },
+        _ => return,
+    };
+    if tool.source.is_some() {
+        warn!("confirm or cancel the current array first");
+        return;
+    }
+    let mut selected = selection.iter().filter(|(_, pick)| pick.is_selected);
+    let (Some((source, _)), None) = (selected.next(), selected.next()) else {
+        warn!("select exactly one entity to make an array of");
+        return;
+    };
+    tool.source = Some(source);
+    tool.kind = kind;
+    if let ArrayKind::Radial { axis } = kind {
+        tool.center_direction = Vec3::new(axis.z, axis.x, axis.y);
+    }
+}
+
+/// Change the array being edited with the arrow keys, Shift and left or right for the radius,
+/// confirm with Enter and cancel with Escape.
+pub(crate) fn array_keys(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut tool: ResMut<ArrayTool>,
+) {
+    if tool.source.is_none() {
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::ArrowUp) {
+        tool.count += 1;
+    }
+    if keyboard.just_pressed(KeyCode::ArrowDown) && tool.count > 1 {
+        tool.count -= 1;
+    }
+    let step = match (
+        keyboard.just_pressed(KeyCode::ArrowRight),
+        keyboard.just_pressed(KeyCode::ArrowLeft),
+    ) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    };
+    if step != 0.0 {
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        tool.nudge(step, shift);
+    }
+    if keyboard.just_pressed(KeyCode::Enter) {
+        commands.trigger(ConfirmArray);
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        commands.trigger(CancelArray);
+    }
+}
+
+/// Spawn, remove and move copies so they match the [`ArrayTool`] and its source.
+pub(crate) fn update_array(
+    mut commands: Commands,
+    mut tool: ResMut<ArrayTool>,
+    sources: Query<Ref<Transform>>,
+) {
+    let Some(source) = tool.source else {
+        return;
+    };
+    let Ok(source_transform) = sources.get(source) else {
+        warn!("array source is gone");
+        commands.trigger(CancelArray);
+        return;
+    };
+    if !tool.is_changed() && !source_transform.is_changed() {
+        return;
+    }
+
+    let tool = tool.bypass_change_detection();
+    // copies despawned elsewhere, from the inspector for example, are replaced
+    tool.copies.retain(|copy| sources.contains(*copy));
+    while tool.copies.len() > tool.count {
+        if let Some(copy) = tool.copies.pop() {
+            commands.entity(copy).try_despawn();
+        }
+    }
+    while tool.copies.len() < tool.count {
+        let copy = commands
+            .entity(source)
+            .clone_and_spawn_with_opt_out(|builder| {
+                builder.linked_cloning(true).add_observers(true);
+            })
+            .insert(PickSelection::default())
+            .id();
+        tool.copies.push(copy);
+    }
+    for (index, copy) in tool.copies.iter().enumerate() {
+        let transform = tool.copy_transform(&source_transform, index + 1);
+        if transform.is_finite() {
+            commands.entity(*copy).try_insert(transform);
+        }
+    }
+}
+
+pub(crate) fn confirm_array(_confirm: On<ConfirmArray>, mut tool: ResMut<ArrayTool>) {
+    info!("array of {} copies", tool.copies.len());
+    tool.source = None;
+    tool.copies.clear();
+}
+
+pub(crate) fn cancel_array(
+    _cancel: On<CancelArray>,
+    mut commands: Commands,
+    mut tool: ResMut<ArrayTool>,
+) {
+    for copy in tool.copies.drain(..) {
+        commands.entity(copy).try_despawn();
+    }
+    tool.source = None;
+}
+
+pub(crate) fn update_array_label(
+    mut commands: Commands,
+    tool: Res<ArrayTool>,
+    pick_cam: Query<Entity, With<GizmoPickSource>>,
+    mut label: Query<(Entity, &mut Text, &mut Node, Option<&UiTargetCamera>), With<ArrayLabel>>,
+) {
+    if !tool.is_changed() {
+        return;
+    }
+    let Ok((label, mut text, mut node, target)) = label.single_mut() else {
+        return;
+    };
+    if tool.source.is_none() {
+        node.display = Display::None;
+        return;
+    }
+    let (values, keys) = match tool.kind {
+        ArrayKind::Linear { .. } => (
+            format!("Array {} x {:.2}", tool.count, tool.spacing),
+            "up/down: count, left/right: spacing",
+        ),
+        ArrayKind::Radial { .. } => {
+            let (count, angle) = (tool.count, tool.angle.to_degrees());
+            (
+                format!(
+                    "Radial array {count} x {angle:.1}° at radius {:.2}",
+                    tool.radius
+                ),
+                "up/down: count, left/right: angle, Shift+left/right: radius",
+            )
+        }
+    };
+    text.0 = format!("{values}   {keys}, Enter: keep, Esc: cancel");
+    node.display = Display::Flex;
+    if let Some(camera) = pick_cam.iter().last()
+        && target.map(UiTargetCamera::entity) != Some(camera)
+    {
+        commands.entity(label).insert(UiTargetCamera(camera));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_copies_follow_the_local_axis() {
+        let tool = ArrayTool {
+            kind: ArrayKind::Linear { axis: Vec3::X },
+            spacing: 2.0,
+            ..default()
+        };
+        let source = Transform::from_xyz(1.0, 0.0, 0.0)
+            .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        let third = tool.copy_transform(&source, 3);
+        assert!(
+            third
+                .translation
+                .abs_diff_eq(Vec3::new(1.0, 0.0, -6.0), 1e-5)
+        );
+        assert_eq!(third.rotation, source.rotation);
+    }
+
+    #[test]
+    fn radial_copies_go_around_the_chosen_center() {
+        let tool = ArrayTool {
+            kind: ArrayKind::Radial { axis: Vec3::Y },
+            angle: std::f32::consts::FRAC_PI_2,
+            radius: 2.0,
+            center_direction: Vec3::Z,
+            ..default()
+        };
+        let source = Transform::from_xyz(0.0, 1.0, 0.0);
+        // the center is 2 along local Z, a quarter turn around Y puts the first copy beside it
+        let first = tool.copy_transform(&source, 1);
+        assert!(
+            first
+                .translation
+                .abs_diff_eq(Vec3::new(-2.0, 1.0, 2.0), 1e-5)
+        );
+        let second = tool.copy_transform(&source, 2);
+        assert!(
+            second
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 1.0, 4.0), 1e-5)
+        );
+        // a full turn comes back to the source
+        let full_turn = tool.copy_transform(&source, 4);
+        assert!(full_turn.translation.abs_diff_eq(source.translation, 1e-5));
+    }
+
+    #[test]
+    fn spacing_and_radius_steps_go_through_zero() {
+        let mut tool = ArrayTool {
+            spacing: 0.2,
+            ..default()
+        };
+        for _ in 0..3 {
+            tool.nudge(-1.0, false);
+        }
+        assert!((tool.spacing + 0.1).abs() < 1e-5);
+
+        let mut tool = ArrayTool {
+            kind: ArrayKind::Radial { axis: Vec3::Y },
+            radius: 0.1,
+            ..default()
+        };
+        let angle = tool.angle;
+        tool.nudge(-2.0, true);
+        assert!((tool.radius + 0.1).abs() < 1e-5);
+        assert_eq!(tool.angle, angle);
+    }
+}
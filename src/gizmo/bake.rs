@@ -0,0 +1,195 @@
+use bevy::{ecs::system::SystemParam, math::Affine3A, prelude::*};
+
+use crate::{
+    gizmo::{PickSelection, TransformGizmo, history::TransformHistory},
+    mesh::bake::transform_mesh,
+};
+
+/// Move the rotation and/or scale of the selected entities into the vertices of their
+/// [`Mesh3d`] and reset those parts of their [`Transform`], without moving anything in world
+/// space. Children are moved so they stay where they are.
+///
+/// Entities whose mesh is not loaded, or whose children would end up sheared, are skipped.
+/// Each baked entity gets its own copy of the mesh, so other entities sharing it are left alone.
+/// The change is not recorded in the [`TransformHistory`], which can't put the vertices back,
+/// and the changes it has of the baked entities and their children are forgotten.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct BakeTransform {
+    pub rotation: bool,
+    pub scale: bool,
+}
+
+impl Default for BakeTransform {
+    fn default() -> Self {
+        Self {
+            rotation: true,
+            scale: true,
+        }
+    }
+}
+
+/// Transform that keeps a child where it is when `delta` moves from its parent into the mesh,
+/// `None` when a child turned against a non-uniform scale would need a shear for that.
+fn child_after_bake(delta: Affine3A, child: &Transform) -> Option<Transform> {
+    let moved = delta * child.compute_affine();
+    let kept = Transform::from_matrix(Mat4::from(moved));
+    kept.compute_affine()
+        .abs_diff_eq(moved, 1e-4)
+        .then_some(kept)
+}
+
+/// Selection, hierarchy and transforms a bake reads and rewrites.
+#[derive(SystemParam)]
+pub(crate) struct BakeItems<'w, 's> {
+    selection: Query<'w, 's, (Entity, &'static PickSelection, Option<&'static Mesh3d>)>,
+    children: Query<'w, 's, &'static Children>,
+    parents: Query<'w, 's, &'static ChildOf>,
+    transforms: Query<'w, 's, &'static mut Transform, Without<TransformGizmo>>,
+}
+
+pub(crate) fn bake_transform(
+    bake: On<BakeTransform>,
+    mut commands: Commands,
+    gizmo: Query<&TransformGizmo>,
+    items: BakeItems,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut history: ResMut<TransformHistory>,
+) {
+    let BakeItems {
+        selection,
+        children,
+        parents,
+        mut transforms,
+    } = items;
+    if gizmo
+        .iter()
+        .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        warn!("not baking during a drag");
+        return;
+    }
+    // parents first, baking a parent gives its children back the rotation and scale it had
+    let mut selected: Vec<_> = selection
+        .iter()
+        .filter(|(_, pick, _)| pick.is_selected)
+        .collect();
+    selected.sort_by_cached_key(|(entity, ..)| parents.iter_ancestors(*entity).count());
+    for (entity, _, mesh) in selected {
+        let Ok(before) = transforms.get(entity).copied() else {
+            continue;
+        };
+        let baked = Transform {
+            rotation: if bake.rotation {
+                Quat::IDENTITY
+            } else {
+                before.rotation
+            },
+            scale: if bake.scale { Vec3::ONE } else { before.scale },
+            ..before
+        };
+        // moves the geometry from the space of `baked` to where it was under `before`
+        let delta = baked.compute_affine().inverse() * before.compute_affine();
+        if !delta.is_finite() || delta.matrix3.determinant() == 0.0 {
+            warn!("can't bake the degenerate transform {:?}", before);
+            continue;
+        }
+        let Some(mut mesh) = mesh.and_then(|mesh| meshes.get(mesh)).cloned() else {
+            warn!("{entity} has no loaded mesh to bake into");
+            continue;
+        };
+        let child_transforms: Option<Vec<(Entity, Transform)>> = children
+            .get(entity)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| Some((*child, *transforms.get(*child).ok()?)))
+            .map(|(child, transform)| Some((child, child_after_bake(delta, &transform)?)))
+            .collect();
+        let Some(child_transforms) = child_transforms else {
+            warn!("can't bake {entity}, its children would need a shear to stay in place");
+            continue;
+        };
+
+        transform_mesh(&mut mesh, delta);
+        commands.entity(entity).insert(Mesh3d(meshes.add(mesh)));
+        if let Ok(mut transform) = transforms.get_mut(entity) {
+            *transform = baked;
+        }
+        // undoing an earlier edit would put back what is now in the mesh
+        history.forget(entity);
+        for (child, kept) in child_transforms {
+            if let Ok(mut transform) = transforms.get_mut(child) {
+                *transform = kept;
+            }
+            history.forget(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gizmo::history::TransformEdit;
+
+    #[test]
+    fn children_stay_in_place_unless_sheared() {
+        let delta = Affine3A::from_scale(Vec3::new(2.0, 1.0, 1.0));
+        let child = Transform::from_xyz(1.0, 2.0, 0.0);
+        let kept = child_after_bake(delta, &child).unwrap();
+        assert!(kept.translation.abs_diff_eq(Vec3::new(2.0, 2.0, 0.0), 1e-5));
+        assert!(kept.scale.abs_diff_eq(Vec3::new(2.0, 1.0, 1.0), 1e-5));
+
+        let turned = child.with_rotation(Quat::from_rotation_z(0.5));
+        assert_eq!(child_after_bake(delta, &turned), None);
+        let uniform = Affine3A::from_scale(Vec3::splat(2.0));
+        assert!(child_after_bake(uniform, &turned).is_some());
+    }
+
+    #[test]
+    fn selected_children_are_baked_after_their_parent() {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<TransformHistory>();
+        world.add_observer(bake_transform);
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(Triangle3d::default()));
+        let selected = PickSelection {
+            is_selected: true,
+            ..default()
+        };
+        // the child is spawned first so it would also be baked first
+        let parent = world.spawn_empty().id();
+        let child = world
+            .spawn((
+                Transform::from_xyz(1.0, 0.0, 0.0).with_rotation(Quat::from_rotation_x(0.3)),
+                Mesh3d(mesh.clone()),
+                selected.clone(),
+                ChildOf(parent),
+            ))
+            .id();
+        world.entity_mut(parent).insert((
+            Transform::from_rotation(Quat::from_rotation_z(0.5)).with_scale(Vec3::splat(2.0)),
+            Mesh3d(mesh),
+            selected,
+        ));
+
+        let mut edit = TransformEdit::new("drag");
+        edit.push(
+            parent,
+            Transform::IDENTITY,
+            Transform::from_xyz(0.0, 1.0, 0.0),
+        );
+        world.resource_mut::<TransformHistory>().record(edit);
+
+        world.trigger(BakeTransform::default());
+        assert!(!world.resource::<TransformHistory>().can_undo());
+        for entity in [parent, child] {
+            let transform = world.get::<Transform>(entity).unwrap();
+            assert!(transform.rotation.abs_diff_eq(Quat::IDENTITY, 1e-5));
+            assert!(transform.scale.abs_diff_eq(Vec3::ONE, 1e-5));
+        }
+        let translation = world.get::<Transform>(child).unwrap().translation;
+        let expected = Quat::from_rotation_z(0.5) * Vec3::new(2.0, 0.0, 0.0);
+        assert!(translation.abs_diff_eq(expected, 1e-5));
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use crate::gizmo::{PickSelection, TransformGizmo, TransformGizmoInteraction};
+
+/// Limits on how the gizmo may change an entity's [`Transform`].
+///
+/// Locks are along the entity's own axes, the ones the gizmo handles show. Position bounds are
+/// in the parent's space and angle limits apply to the YXZ Euler angles of the rotation.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct TransformConstraints {
+    pub lock_translation: BVec3,
+    pub lock_rotation: BVec3,
+    pub lock_scale: BVec3,
+    pub min_translation: Vec3,
+    pub max_translation: Vec3,
+    /// Smallest Euler angles in radians.
+    pub min_angles: Vec3,
+    /// Largest Euler angles in radians.
+    pub max_angles: Vec3,
+    pub min_scale: Vec3,
+    pub max_scale: Vec3,
+}
+
+impl Default for TransformConstraints {
+    fn default() -> Self {
+        Self {
+            lock_translation: BVec3::FALSE,
+            lock_rotation: BVec3::FALSE,
+            lock_scale: BVec3::FALSE,
+            min_translation: Vec3::NEG_INFINITY,
+            max_translation: Vec3::INFINITY,
+            min_angles: Vec3::NEG_INFINITY,
+            max_angles: Vec3::INFINITY,
+            min_scale: Vec3::ZERO,
+            max_scale: Vec3::INFINITY,
+        }
+    }
+}
+
+impl TransformConstraints {
+    /// Only rotation around the local Y axis between `min_angle` and `max_angle` radians, like
+    /// a door on its hinges.
+    pub fn hinge_y(min_angle: f32, max_angle: f32) -> Self {
+        Self {
+            lock_translation: BVec3::TRUE,
+            lock_rotation: BVec3::new(true, false, true),
+            lock_scale: BVec3::TRUE,
+            min_angles: Vec3::new(f32::NEG_INFINITY, min_angle, f32::NEG_INFINITY),
+            max_angles: Vec3::new(f32::INFINITY, max_angle, f32::INFINITY),
+            ..default()
+        }
+    }
+
+    /// The closest transform to `proposed` these constraints allow, for a drag that started at
+    /// `initial`.
+    pub fn apply(&self, initial: &Transform, proposed: Transform) -> Transform {
+        let unlocked = |locks: BVec3| Vec3::select(locks, Vec3::ZERO, Vec3::ONE);
+
+        let local_move = initial.rotation.inverse() * (proposed.translation - initial.translation);
+        let translation = (initial.translation
+            + initial.rotation * (local_move * unlocked(self.lock_translation)))
+        .clamp(self.min_translation, self.max_translation);
+
+        let local_turn = (initial.rotation.inverse() * proposed.rotation).to_scaled_axis();
+        let mut rotation =
+            initial.rotation * Quat::from_scaled_axis(local_turn * unlocked(self.lock_rotation));
+        if self.min_angles.cmpgt(Vec3::NEG_INFINITY).any()
+            || self.max_angles.cmplt(Vec3::INFINITY).any()
+        {
+            let (yaw, pitch, roll) = rotation.to_euler(EulerRot::YXZ);
+            let angles = Vec3::new(pitch, yaw, roll).clamp(self.min_angles, self.max_angles);
+            rotation = Quat::from_euler(EulerRot::YXZ, angles.y, angles.x, angles.z);
+        }
+
+        let scale = Vec3::select(self.lock_scale, initial.scale, proposed.scale)
+            .clamp(self.min_scale, self.max_scale);
+
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+}
+
+/// Whether a handle only changes axes that are locked.
+fn handle_locked(
+    interaction: &TransformGizmoInteraction,
+    constraints: &TransformConstraints,
+) -> bool {
+    let locked = |locks: BVec3, axis: Vec3| {
+        Vec3::select(locks, Vec3::ZERO, axis.abs()).length_squared() == 0.0
+    };
+    match *interaction {
+        TransformGizmoInteraction::TranslateAxis { original, .. } => {
+            locked(constraints.lock_translation, original)
+        }
+        TransformGizmoInteraction::TranslatePlane { original, normal } => {
+            if original == Vec3::ZERO {
+                constraints.lock_translation.all()
+            } else {
+                locked(constraints.lock_translation, Vec3::ONE - normal.abs())
+            }
+        }
+        TransformGizmoInteraction::RotateAxis { original, .. } => {
+            locked(constraints.lock_rotation, original)
+        }
+        TransformGizmoInteraction::ScaleAxis { original, .. } => {
+            locked(constraints.lock_scale, original)
+        }
+        TransformGizmoInteraction::RotateView | TransformGizmoInteraction::Trackball => {
+            constraints.lock_rotation.all()
+        }
+    }
+}
+
+/// Hide the handles whose axes are locked on any selected entity.
+pub(crate) fn hide_locked_handles(
+    selection: Query<(&PickSelection, &TransformConstraints)>,
+    gizmo: Query<&TransformGizmo>,
+    mut handles: Query<(&TransformGizmoInteraction, &mut Visibility)>,
+) {
+    // leave the handle being dragged alone
+    if gizmo
+        .iter()
+        .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        return;
+    }
+    for (interaction, mut visibility) in handles.iter_mut() {
+        let locked = selection
+            .iter()
+            .any(|(pick, constraints)| pick.is_selected && handle_locked(interaction, constraints));
+        let wanted = if locked {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locked_translation_axis_does_not_move() {
+        let constraints = TransformConstraints {
+            lock_translation: BVec3::new(false, true, false),
+            ..default()
+        };
+        let initial = Transform::from_xyz(1.0, 2.0, 3.0);
+        let moved = constraints.apply(&initial, Transform::from_xyz(2.0, 5.0, 4.0));
+        assert!(
+            moved
+                .translation
+                .abs_diff_eq(Vec3::new(2.0, 2.0, 4.0), 1e-5)
+        );
+    }
+
+    #[test]
+    fn translation_is_kept_in_bounds() {
+        let constraints = TransformConstraints {
+            min_translation: Vec3::splat(-1.0),
+            max_translation: Vec3::splat(1.0),
+            ..default()
+        };
+        let moved = constraints.apply(&Transform::IDENTITY, Transform::from_xyz(5.0, -5.0, 0.5));
+        assert_eq!(moved.translation, Vec3::new(1.0, -1.0, 0.5));
+    }
+
+    #[test]
+    fn hinge_only_turns_around_y_within_limits() {
+        let constraints = TransformConstraints::hinge_y(-90f32.to_radians(), 90f32.to_radians());
+        let proposed = Transform::from_rotation(
+            Quat::from_rotation_y(120f32.to_radians()) * Quat::from_rotation_x(0.5),
+        );
+        let moved = constraints.apply(&Transform::IDENTITY, proposed);
+        let (y, x, z) = moved.rotation.to_euler(EulerRot::YXZ);
+        assert!(x.abs() < 1e-4 && z.abs() < 1e-4);
+        assert!((y - 90f32.to_radians()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn scale_is_kept_in_range_and_locks() {
+        let constraints = TransformConstraints {
+            lock_scale: BVec3::new(true, false, false),
+            min_scale: Vec3::splat(0.5),
+            max_scale: Vec3::splat(2.0),
+            ..default()
+        };
+        let proposed = Transform::from_scale(Vec3::new(3.0, 3.0, 0.1));
+        let moved = constraints.apply(&Transform::IDENTITY, proposed);
+        assert_eq!(moved.scale, Vec3::new(1.0, 2.0, 0.5));
+    }
+}
@@ -91,17 +91,16 @@ impl RotateDebugVectors {
             WhichRotateVector::PickingRay => *self.picking_ray.direction,
             WhichRotateVector::ArcCosine => {
                 Vec3::new(self.arccosine, self.arccosine.to_degrees(), 0.0)
-            }
-            /*
-                        WhichRotateVector::RotationAxis => self.rotation_axis,
-                        WhichRotateVector::VerticalVector => self.vertical_vector,
-                        WhichRotateVector::PlaneNormal => self.plane_normal,
-                        WhichRotateVector::PlaneOrigin => self.plane_origin,
-                        WhichRotateVector::RayPlaneIntersection => self.ray_plane_intersection,
-                        WhichRotateVector::Dot => Vec3::splat(self.dot.to_degrees()),
-                        WhichRotateVector::Det => Vec3::splat(self.det.to_degrees()),
-                        WhichRotateVector::Angle => Vec3::splat(self.angle.to_degrees()),
-            */
+            } /*
+                          WhichRotateVector::RotationAxis => self.rotation_axis,
+                          WhichRotateVector::VerticalVector => self.vertical_vector,
+                          WhichRotateVector::PlaneNormal => self.plane_normal,
+                          WhichRotateVector::PlaneOrigin => self.plane_origin,
+                          WhichRotateVector::RayPlaneIntersection => self.ray_plane_intersection,
+                          WhichRotateVector::Dot => Vec3::splat(self.dot.to_degrees()),
+                          WhichRotateVector::Det => Vec3::splat(self.det.to_degrees()),
+                          WhichRotateVector::Angle => Vec3::splat(self.angle.to_degrees()),
+              */
         }
     }
 }
@@ -318,6 +317,8 @@ pub fn debug_ui(
                         original: _,
                         axis: _,
                     } => {}
+                    crate::gizmo::TransformGizmoInteraction::RotateView
+                    | crate::gizmo::TransformGizmoInteraction::Trackball => {}
                 },
                 None => {
                     ui.label("No gizmo interaction");
@@ -542,6 +543,14 @@ pub fn hide_drag_vectors(
                     *visibility = Visibility::Inherited;
                 }
             }
+            RotateView | Trackball => {
+                for (_vector, _transform, mut visibility) in axis_query.iter_mut() {
+                    *visibility = Visibility::Hidden;
+                }
+                for (_vector, _transform, mut visibility) in rotate_query.iter_mut() {
+                    *visibility = Visibility::Hidden;
+                }
+            }
         }
     }
 }
@@ -570,8 +579,10 @@ pub fn update_debug_vectors(
             WhichDebugVector::CursorVector => {
                 let local_forward = Vec3::NEG_Y;
                 transform.translation = vectors.ray_plane_intersection;
-                let norm = vectors.cursor_vector.normalize();
-                transform.rotation = Quat::from_rotation_arc(local_forward, norm);
+                // a zero length cursor vector has no direction, keep the last one
+                if let Some(norm) = vectors.cursor_vector.try_normalize() {
+                    transform.rotation = Quat::from_rotation_arc(local_forward, norm);
+                }
 
                 // let cursor_vector: Vec3 = ray_plane_intersection - plane_origin;
                 // vectors.ray_plane_intersection - vectors.plane_origin;
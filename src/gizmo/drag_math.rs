@@ -0,0 +1,394 @@
+//! Ray and screen math for dragging the gizmo that returns `None` instead of NaN or infinity
+//! when the configuration is degenerate, so the caller can fall back or skip the frame.
+
+use bevy::prelude::*;
+
+/// Below this sine the picking ray is treated as parallel to the dragged axis.
+pub const PARALLEL_EPSILON: f32 = 1e-3;
+/// Below this cosine the picking ray is treated as grazing the drag plane.
+pub const GRAZING_EPSILON: f32 = 1e-3;
+/// The pointer has to be at least this many pixels away from the center to measure an angle.
+pub const MIN_SCREEN_RADIUS: f32 = 1.0;
+/// An axis shorter than this many pixels on screen can't be dragged in screen space.
+pub const MIN_SCREEN_AXIS_LENGTH: f32 = 1e-2;
+/// Trackball rotation for each pixel the pointer is dragged.
+pub const TRACKBALL_RADIANS_PER_PIXEL: f32 = 0.01;
+
+/// Vector perpendicular to the picking ray and the axis, and the normal of the plane containing
+/// the axis that faces the ray as much as possible. `None` when the ray runs along the axis.
+pub fn axis_drag_plane(ray_direction: Vec3, axis: Vec3) -> Option<(Vec3, Vec3)> {
+    let axis = axis.try_normalize()?;
+    let ray_direction = ray_direction.try_normalize()?;
+    let cross = ray_direction.cross(axis);
+    if cross.length() < PARALLEL_EPSILON {
+        return None;
+    }
+    let vertical_vector = cross.normalize();
+    let plane_normal = axis.cross(vertical_vector).try_normalize()?;
+    Some((vertical_vector, plane_normal))
+}
+
+/// Point where the ray hits the plane, `None` if the ray grazes the plane, hits it behind its
+/// origin or the result isn't finite.
+pub fn intersect_plane(ray: Ray3d, plane_normal: Vec3, plane_origin: Vec3) -> Option<Vec3> {
+    let plane_normal = plane_normal.try_normalize()?;
+    let denominator = ray.direction.dot(plane_normal);
+    if denominator.abs() < GRAZING_EPSILON {
+        return None;
+    }
+    let point_to_point = plane_origin - ray.origin;
+    let intersect_dist = plane_normal.dot(point_to_point) / denominator;
+    if intersect_dist < 0.0 {
+        return None;
+    }
+    let intersect_position = ray.direction * intersect_dist + ray.origin;
+    intersect_position.is_finite().then_some(intersect_position)
+}
+
+/// Distance along an axis for a pointer drag, measured on screen.
+///
+/// `origin` and `axis_end` are the screen positions of the axis start and of one unit along it,
+/// so the result is in world units. `None` when the axis points at the camera.
+pub fn screen_axis_distance(
+    origin: Vec2,
+    axis_end: Vec2,
+    start: Vec2,
+    current: Vec2,
+) -> Option<f32> {
+    let screen_axis = axis_end - origin;
+    let length_squared = screen_axis.length_squared();
+    if !length_squared.is_finite() || length_squared < MIN_SCREEN_AXIS_LENGTH.powi(2) {
+        return None;
+    }
+    let distance = (current - start).dot(screen_axis) / length_squared;
+    distance.is_finite().then_some(distance)
+}
+
+/// Remove the part of `vector` along `normal`, so a translation stays in the plane.
+pub fn project_onto_plane(vector: Vec3, normal: Vec3) -> Option<Vec3> {
+    let normal = normal.try_normalize()?;
+    let projected = vector - normal * vector.dot(normal);
+    projected.is_finite().then_some(projected)
+}
+
+/// Signed angle from `start` to `current` around the screen center they are relative to,
+/// `None` when either is too close to the center to have a direction.
+pub fn screen_angle(start: Vec2, current: Vec2) -> Option<f32> {
+    if !start.is_finite()
+        || !current.is_finite()
+        || start.length() < MIN_SCREEN_RADIUS
+        || current.length() < MIN_SCREEN_RADIUS
+    {
+        return None;
+    }
+    let angle = start.angle_to(current);
+    angle.is_finite().then_some(angle)
+}
+
+/// Angle a pointer has turned around a point on screen, summed up move by move so it keeps
+/// growing past half a turn instead of flipping like the angle from the start would.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScreenAngle {
+    /// Pointer position the angle was last measured at.
+    pointer: Vec2,
+    angle: f32,
+}
+
+impl ScreenAngle {
+    pub fn new(pointer: Vec2) -> Self {
+        Self {
+            pointer,
+            angle: 0.0,
+        }
+    }
+
+    /// Add the turn from the last pointer to `pointer` around `center`, both in window
+    /// coordinates with y pointing down, and return the total counter-clockwise angle. `None`
+    /// when either is too close to the center, the next move is then measured from the last
+    /// pointer with a direction.
+    pub fn update(&mut self, center: Vec2, pointer: Vec2) -> Option<f32> {
+        let relative = |position: Vec2| {
+            let relative = position - center;
+            Vec2::new(relative.x, -relative.y)
+        };
+        let step = screen_angle(relative(self.pointer), relative(pointer))?;
+        self.pointer = pointer;
+        self.angle += step;
+        Some(self.angle)
+    }
+}
+
+/// Rotation for a trackball drag of `delta` pixels, screen y pointing down, as if the pointer
+/// rolled the front of a ball. `right` and `up` are the camera axes in world space.
+pub fn trackball_rotation(delta: Vec2, right: Vec3, up: Vec3) -> Option<Quat> {
+    if !delta.is_finite() {
+        return None;
+    }
+    let angle = delta.length() * TRACKBALL_RADIANS_PER_PIXEL;
+    if angle == 0.0 {
+        return Some(Quat::IDENTITY);
+    }
+    // dragging right turns around up, dragging down turns around right
+    let axis = (up * delta.x + right * delta.y).try_normalize()?;
+    Some(Quat::from_axis_angle(axis, angle))
+}
+
+/// Where to move a pointer that got within `margin` pixels of the edge of a `size` window so
+/// it comes back in on the opposite side, `None` while it is far enough from the edges.
+pub fn wrap_position(position: Vec2, size: Vec2, margin: f32) -> Option<Vec2> {
+    if !position.is_finite() || size.min_element() <= 4.0 * margin {
+        return None;
+    }
+    let wrap = |value: f32, size: f32| {
+        if value < margin {
+            value + size - 3.0 * margin
+        } else if value > size - margin {
+            value - size + 3.0 * margin
+        } else {
+            value
+        }
+    };
+    let wrapped = Vec2::new(wrap(position.x, size.x), wrap(position.y, size.y));
+    (wrapped != position).then_some(wrapped)
+}
+
+/// `rotation` turned the shortest way so its local `axis` points along `normal`.
+pub fn align_axis(rotation: Quat, axis: Vec3, normal: Vec3) -> Option<Quat> {
+    let from = (rotation * axis).try_normalize()?;
+    let normal = normal.try_normalize()?;
+    Some(Quat::from_rotation_arc(from, normal) * rotation)
+}
+
+/// Only the yaw of `rotation` around world Y, tilted the shortest way so its local `axis` points
+/// along `normal`. Aligning twice gives the same result, unlike [`align_axis`] which depends on
+/// the pitch and roll it starts from.
+pub fn align_axis_keep_yaw(rotation: Quat, axis: Vec3, normal: Vec3) -> Option<Quat> {
+    let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+    let heading = Quat::from_rotation_y(yaw);
+    align_axis(heading, axis, normal)
+}
+
+/// Overwrite `target` only when every component of `new` is finite, returns whether it was
+/// written.
+pub fn write_finite(target: &mut Transform, new: Transform) -> bool {
+    if new.is_finite() {
+        *target = new;
+        true
+    } else {
+        warn!("refusing to write a non-finite transform {:?}", new);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: Vec3, direction: Vec3) -> Ray3d {
+        Ray3d::new(origin, Dir3::new(direction).unwrap())
+    }
+
+    #[test]
+    fn axis_drag_plane_faces_the_ray() {
+        let (vertical, normal) = axis_drag_plane(Vec3::NEG_Z, Vec3::X).unwrap();
+        assert!(vertical.is_normalized());
+        assert!(normal.abs().abs_diff_eq(Vec3::Z, 1e-6));
+    }
+
+    #[test]
+    fn axis_drag_plane_rejects_ray_along_axis() {
+        assert_eq!(axis_drag_plane(Vec3::X, Vec3::X), None);
+        assert_eq!(axis_drag_plane(Vec3::NEG_X, Vec3::X), None);
+        assert_eq!(axis_drag_plane(Vec3::new(1.0, 1e-5, 0.0), Vec3::X), None);
+    }
+
+    #[test]
+    fn axis_drag_plane_rejects_zero_vectors() {
+        assert_eq!(axis_drag_plane(Vec3::NEG_Z, Vec3::ZERO), None);
+        assert_eq!(axis_drag_plane(Vec3::ZERO, Vec3::X), None);
+        assert_eq!(axis_drag_plane(Vec3::NAN, Vec3::X), None);
+    }
+
+    #[test]
+    fn intersect_plane_hits_in_front() {
+        let hit = intersect_plane(
+            ray(Vec3::new(1.0, 2.0, 5.0), Vec3::NEG_Z),
+            Vec3::Z,
+            Vec3::ZERO,
+        );
+        assert_eq!(hit, Some(Vec3::new(1.0, 2.0, 0.0)));
+    }
+
+    #[test]
+    fn intersect_plane_rejects_grazing_ray() {
+        let hit = intersect_plane(
+            ray(Vec3::Z, Vec3::new(1.0, 0.0, -1e-5)),
+            Vec3::Z,
+            Vec3::ZERO,
+        );
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn intersect_plane_rejects_plane_behind_ray() {
+        let hit = intersect_plane(ray(Vec3::Z, Vec3::Z), Vec3::Z, Vec3::ZERO);
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn intersect_plane_rejects_zero_normal() {
+        let hit = intersect_plane(ray(Vec3::Z, Vec3::NEG_Z), Vec3::ZERO, Vec3::ZERO);
+        assert_eq!(hit, None);
+    }
+
+    #[test]
+    fn screen_axis_distance_is_in_world_units() {
+        // one world unit is 100 pixels to the right
+        let distance = screen_axis_distance(
+            Vec2::ZERO,
+            Vec2::new(100.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(260.0, 40.0),
+        );
+        assert_eq!(distance, Some(2.5));
+    }
+
+    #[test]
+    fn screen_axis_distance_rejects_axis_pointing_at_camera() {
+        let origin = Vec2::new(50.0, 50.0);
+        assert_eq!(
+            screen_axis_distance(origin, origin, Vec2::ZERO, Vec2::ONE),
+            None
+        );
+        assert_eq!(
+            screen_axis_distance(origin, origin + Vec2::splat(1e-4), Vec2::ZERO, Vec2::ONE),
+            None
+        );
+        assert_eq!(
+            screen_axis_distance(origin, Vec2::NAN, Vec2::ZERO, Vec2::ONE),
+            None
+        );
+    }
+
+    #[test]
+    fn project_onto_plane_removes_normal_part() {
+        let projected = project_onto_plane(Vec3::new(1.0, 2.0, 3.0), Vec3::Y * 2.0);
+        assert_eq!(projected, Some(Vec3::new(1.0, 0.0, 3.0)));
+        assert_eq!(project_onto_plane(Vec3::ONE, Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn screen_angle_keeps_adding_up_past_half_a_turn() {
+        let center = Vec2::new(100.0, 100.0);
+        let mut angle = ScreenAngle::new(center + Vec2::new(50.0, 0.0));
+        let mut total = 0.0;
+        // one and a half turns counter-clockwise on screen, y down
+        for step in 1..=24 {
+            let turn = step as f32 * std::f32::consts::PI / 8.0;
+            let pointer = center + 50.0 * Vec2::new(turn.cos(), -turn.sin());
+            total = angle.update(center, pointer).unwrap();
+        }
+        assert!((total - 3.0 * std::f32::consts::PI).abs() < 1e-3);
+        assert_eq!(angle.update(center, center), None);
+    }
+
+    #[test]
+    fn screen_angle_is_signed() {
+        let angle = screen_angle(Vec2::new(10.0, 0.0), Vec2::new(0.0, 10.0)).unwrap();
+        assert!((angle - std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+        let angle = screen_angle(Vec2::new(0.0, 10.0), Vec2::new(10.0, 0.0)).unwrap();
+        assert!((angle + std::f32::consts::FRAC_PI_2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn screen_angle_rejects_pointer_on_center() {
+        assert_eq!(screen_angle(Vec2::ZERO, Vec2::new(10.0, 0.0)), None);
+        assert_eq!(
+            screen_angle(Vec2::new(10.0, 0.0), Vec2::new(0.1, 0.0)),
+            None
+        );
+        assert_eq!(screen_angle(Vec2::new(10.0, 0.0), Vec2::INFINITY), None);
+    }
+
+    #[test]
+    fn trackball_rolls_the_front_with_the_pointer() {
+        // camera looking down -Z, the front of the ball is +Z
+        let right_drag = trackball_rotation(Vec2::new(100.0, 0.0), Vec3::X, Vec3::Y).unwrap();
+        assert!((right_drag * Vec3::Z).x > 0.0);
+        let down_drag = trackball_rotation(Vec2::new(0.0, 100.0), Vec3::X, Vec3::Y).unwrap();
+        assert!((down_drag * Vec3::Z).y < 0.0);
+    }
+
+    #[test]
+    fn trackball_handles_degenerate_input() {
+        assert_eq!(
+            trackball_rotation(Vec2::ZERO, Vec3::X, Vec3::Y),
+            Some(Quat::IDENTITY)
+        );
+        assert_eq!(trackball_rotation(Vec2::NAN, Vec3::X, Vec3::Y), None);
+        assert_eq!(trackball_rotation(Vec2::ONE, Vec3::ZERO, Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn wrap_position_jumps_to_the_opposite_edge() {
+        let size = Vec2::new(800.0, 600.0);
+        assert_eq!(wrap_position(Vec2::new(400.0, 300.0), size, 2.0), None);
+        assert_eq!(
+            wrap_position(Vec2::new(799.0, 300.0), size, 2.0),
+            Some(Vec2::new(5.0, 300.0))
+        );
+        assert_eq!(
+            wrap_position(Vec2::new(1.0, 0.0), size, 2.0),
+            Some(Vec2::new(795.0, 594.0))
+        );
+    }
+
+    #[test]
+    fn wrap_position_ignores_tiny_windows_and_nan() {
+        assert_eq!(wrap_position(Vec2::ZERO, Vec2::splat(4.0), 2.0), None);
+        assert_eq!(wrap_position(Vec2::NAN, Vec2::splat(400.0), 2.0), None);
+    }
+
+    #[test]
+    fn align_axis_points_local_axis_along_normal() {
+        let rotation = Quat::from_rotation_y(0.7);
+        let aligned = align_axis(rotation, Vec3::Y, Vec3::new(1.0, 1.0, 0.0)).unwrap();
+        assert!((aligned * Vec3::Y).abs_diff_eq(Vec3::new(1.0, 1.0, 0.0).normalize(), 1e-5));
+        // already upright stays put
+        let upright = align_axis(rotation, Vec3::Y, Vec3::Y * 3.0).unwrap();
+        assert!(upright.abs_diff_eq(rotation, 1e-5));
+        assert_eq!(align_axis(rotation, Vec3::Y, Vec3::ZERO), None);
+        assert_eq!(align_axis(rotation, Vec3::ZERO, Vec3::Y), None);
+    }
+
+    #[test]
+    fn align_axis_keep_yaw_drops_pitch_and_roll() {
+        let heading = Quat::from_rotation_y(0.7);
+        let tilted = heading * Quat::from_rotation_x(0.4) * Quat::from_rotation_z(-0.2);
+        let aligned = align_axis_keep_yaw(tilted, Vec3::Y, Vec3::Y).unwrap();
+        assert!(aligned.abs_diff_eq(heading, 1e-5));
+        // a wall facing +X, the back of the sign against it
+        let on_wall = align_axis_keep_yaw(tilted, Vec3::Z, Vec3::X).unwrap();
+        assert!((on_wall * Vec3::Z).abs_diff_eq(Vec3::X, 1e-5));
+    }
+
+    #[test]
+    fn write_finite_keeps_target_on_nan() {
+        let original = Transform::from_xyz(1.0, 2.0, 3.0);
+        let mut target = original;
+        assert!(!write_finite(
+            &mut target,
+            Transform::from_xyz(f32::NAN, 0.0, 0.0)
+        ));
+        assert!(!write_finite(
+            &mut target,
+            Transform::from_rotation(Quat::from_xyzw(f32::INFINITY, 0.0, 0.0, 1.0))
+        ));
+        assert!(!write_finite(&mut target, Transform::from_scale(Vec3::NAN)));
+        assert_eq!(target, original);
+
+        let moved = Transform::from_xyz(4.0, 5.0, 6.0);
+        assert!(write_finite(&mut target, moved));
+        assert_eq!(target, moved);
+    }
+}
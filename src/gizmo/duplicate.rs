@@ -0,0 +1,226 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::gizmo::{
+    DragInput, GizmoPickSource, PickSelection, SelectionQuery, TransformGizmo,
+    TransformGizmoInteraction,
+    array::ArrayTool,
+    drag_math::intersect_plane,
+    history::{TransformEdit, TransformHistory},
+    ray_from_screenspace, update_selection,
+};
+
+/// Spawn copies of the selected entities with their children, every component that is [`Clone`]
+/// or registered for reflection and the entity observers, then select the copies and
+/// [grab](GrabSelection) them.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct DuplicateSelection;
+
+/// Move the selection in the view plane with the mouse, without holding a button, until a left
+/// click or Enter puts it down or a right click or Escape puts it back.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct GrabSelection;
+
+/// Shift+D duplicates.
+pub(crate) fn duplicate_key(mut commands: Commands, keyboard: Res<ButtonInput<KeyCode>>) {
+    if keyboard.just_pressed(KeyCode::KeyD)
+        && keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        && !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        commands.trigger(DuplicateSelection);
+    }
+}
+
+pub(crate) fn duplicate_selection(
+    _duplicate: On<DuplicateSelection>,
+    mut commands: Commands,
+    gizmo: Query<&TransformGizmo>,
+    mut selection: Query<(Entity, &mut PickSelection)>,
+    parents: Query<&ChildOf>,
+    array: Res<ArrayTool>,
+) {
+    if gizmo
+        .iter()
+        .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        warn!("not duplicating during a drag");
+        return;
+    }
+    // the grab that follows would share Enter and Escape with the array
+    if array.source.is_some() {
+        warn!("confirm or cancel the current array first");
+        return;
+    }
+    let selected = |entity: Entity| {
+        selection
+            .get(entity)
+            .is_ok_and(|(_, pick)| pick.is_selected)
+    };
+    // children of selected entities are copied along with their parent
+    let roots: Vec<Entity> = selection
+        .iter()
+        .filter(|(entity, pick)| pick.is_selected && !parents.iter_ancestors(*entity).any(selected))
+        .map(|(entity, _)| entity)
+        .collect();
+    if roots.is_empty() {
+        return;
+    }
+
+    for (_, mut pick) in selection.iter_mut() {
+        pick.is_selected = false;
+    }
+    for root in roots {
+        commands
+            .entity(root)
+            .clone_and_spawn_with_opt_out(|builder| {
+                builder.linked_cloning(true).add_observers(true);
+            })
+            .insert(PickSelection {
+                is_selected: true,
+                ..default()
+            });
+    }
+    commands.trigger(GrabSelection);
+}
+
+pub(crate) fn start_grab(
+    _grab: On<GrabSelection>,
+    pick_cam: Query<(&Camera, &GlobalTransform), With<GizmoPickSource>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut gizmo: Query<(&Transform, &mut TransformGizmo)>,
+    mut selection: Query<(&Transform, &mut PickSelection), Without<TransformGizmo>>,
+    array: Res<ArrayTool>,
+) {
+    let Ok((gizmo_transform, mut gizmo)) = gizmo.single_mut() else {
+        warn!("getting main gizmo error");
+        return;
+    };
+    if gizmo.current_interaction.is_some() {
+        return;
+    }
+    if array.source.is_some() {
+        warn!("confirm or cancel the current array first");
+        return;
+    }
+    let Some((camera, camera_transform)) = pick_cam.iter().last() else {
+        warn!("Not exactly one picking camera.");
+        return;
+    };
+    let Ok(window) = windows.single() else {
+        warn!("no window");
+        return;
+    };
+    // the selection keeps its distance to the mouse instead of jumping its center onto it
+    let start = window
+        .cursor_position()
+        .and_then(|cursor| ray_from_screenspace(cursor, camera, camera_transform, window))
+        .and_then(|ray| {
+            intersect_plane(
+                ray,
+                camera_transform.forward().as_vec3(),
+                gizmo_transform.translation,
+            )
+        });
+    let Some(start) = start else {
+        warn!("mouse is not over the view");
+        return;
+    };
+
+    for (transform, mut pick) in selection.iter_mut().filter(|(_, pick)| pick.is_selected) {
+        pick.initial_transform = *transform;
+    }
+    gizmo.current_interaction = Some(TransformGizmoInteraction::TranslatePlane {
+        original: Vec3::ZERO,
+        normal: Vec3::ZERO,
+    });
+    gizmo.drag_start = Some(start);
+    gizmo.initial_transform = *gizmo_transform;
+    gizmo.grabbing = true;
+}
+
+/// Follow the mouse while grabbing and end the grab on a click or key.
+pub(crate) fn update_grab(
+    input: DragInput,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut gizmo: Query<(&mut Transform, &mut TransformGizmo)>,
+    mut items: SelectionQuery,
+    mut history: ResMut<TransformHistory>,
+) {
+    let Ok((mut gizmo_transform, mut gizmo)) = gizmo.single_mut() else {
+        return;
+    };
+    if !gizmo.grabbing {
+        return;
+    }
+
+    if gizmo.grab_released {
+        if !mouse.any_pressed([MouseButton::Left, MouseButton::Right]) {
+            end_grab(&mut gizmo);
+        }
+        return;
+    }
+
+    let keyboard = &input.keyboard;
+    let clicked = mouse.any_just_pressed([MouseButton::Left, MouseButton::Right]);
+    if mouse.just_pressed(MouseButton::Right) || keyboard.just_pressed(KeyCode::Escape) {
+        update_selection(&mut items, &input.spaces, |initial| *initial);
+        *gizmo_transform = gizmo.initial_transform;
+        release_grab(&mut gizmo, clicked);
+        return;
+    }
+    if mouse.just_pressed(MouseButton::Left) || keyboard.just_pressed(KeyCode::Enter) {
+        let mut edit = TransformEdit::new("grab");
+        let selected = items.iter().filter(|(_, _, pick, ..)| pick.is_selected);
+        for (entity, transform, pick, ..) in selected {
+            edit.push(entity, pick.initial_transform, *transform);
+        }
+        if !input.settings.affect_origin_only {
+            history.record(edit);
+        }
+        release_grab(&mut gizmo, clicked);
+        return;
+    }
+
+    let (Some((camera, camera_transform)), Ok(window), Some(start)) = (
+        input.pick_cam.iter().last(),
+        input.windows.single(),
+        gizmo.drag_start,
+    ) else {
+        return;
+    };
+    let hit = window
+        .cursor_position()
+        .and_then(|cursor| ray_from_screenspace(cursor, camera, camera_transform, window))
+        .and_then(|ray| intersect_plane(ray, camera_transform.forward().as_vec3(), start));
+    let Some(hit) = hit else {
+        return;
+    };
+    let mut translation = hit - start;
+    if let Some(grid) = &input.grid {
+        let origin = gizmo.initial_transform.translation;
+        translation = grid.snap_translation(origin, translation, None);
+    }
+    gizmo_transform.translation = gizmo.initial_transform.translation + translation;
+    if let Some(follow) = update_selection(&mut items, &input.spaces, |initial| Transform {
+        translation: initial.translation + translation,
+        ..*initial
+    }) {
+        gizmo_transform.translation = follow.translation;
+    }
+}
+
+// Ends the grab, or when a mouse button ended it, waits for that button to be released so its
+// click still sees the grab
+fn release_grab(gizmo: &mut TransformGizmo, clicked: bool) {
+    if clicked {
+        gizmo.grab_released = true;
+    } else {
+        end_grab(gizmo);
+    }
+}
+
+fn end_grab(gizmo: &mut TransformGizmo) {
+    gizmo.grabbing = false;
+    gizmo.grab_released = false;
+    gizmo.current_interaction = None;
+    gizmo.drag_start = None;
+}
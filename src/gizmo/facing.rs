@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+
+use crate::{
+    gizmo::{GizmoPickSource, TransformGizmo, TransformGizmoInteraction},
+    gizmo_material::GizmoMaterial,
+};
+
+// matches axis_fade in gizmo_material.wgsl
+const AXIS_FADE_START: f32 = 0.9;
+const AXIS_FADE_END: f32 = 0.98;
+// plane handles seen closer to edge-on than this fade out
+const PLANE_FADE_START: f32 = 0.25;
+const PLANE_FADE_END: f32 = 0.1;
+/// Handles faded below this opacity can't be picked, their drag math is unstable.
+const MIN_PICKABLE_FADE: f32 = 0.5;
+/// Distance the trackball disc is pushed away from the camera, past every other handle.
+const TRACKBALL_DEPTH: f32 = 1.5;
+
+/// Transform a handle was spawned with, before it is flipped towards the camera.
+#[derive(Component)]
+pub(crate) struct HandleRest(Transform);
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// How visible a translation handle is from `to_camera`, 0 when it is seen end-on or edge-on.
+/// The view sphere and rotation arcs are always visible.
+fn handle_fade(interaction: &TransformGizmoInteraction, rotation: Quat, to_camera: Vec3) -> f32 {
+    match *interaction {
+        TransformGizmoInteraction::TranslateAxis { original, .. } => {
+            let alignment = (rotation * original).dot(to_camera).abs();
+            1.0 - smoothstep(AXIS_FADE_START, AXIS_FADE_END, alignment)
+        }
+        TransformGizmoInteraction::TranslatePlane { original, normal }
+            if original != Vec3::ZERO =>
+        {
+            let alignment = (rotation * normal).dot(to_camera).abs();
+            smoothstep(PLANE_FADE_END, PLANE_FADE_START, alignment)
+        }
+        _ => 1.0,
+    }
+}
+
+/// Mirror the arrows and plane quads through the gizmo center so they sit on the side facing the
+/// camera, like Blender and Unity do.
+pub(crate) fn flip_handles(
+    mut commands: Commands,
+    gizmo: Query<(&GlobalTransform, &TransformGizmo)>,
+    pick_cam: Query<&GlobalTransform, With<GizmoPickSource>>,
+    mut handles: Query<
+        (
+            Entity,
+            &TransformGizmoInteraction,
+            &mut Transform,
+            Option<&HandleRest>,
+        ),
+        Without<TransformGizmo>,
+    >,
+) {
+    let Ok((gizmo_transform, gizmo)) = gizmo.single() else {
+        return;
+    };
+    // flipping the handle being dragged would move it away from the pointer
+    if gizmo.current_interaction.is_some() {
+        return;
+    }
+    let Some(camera_transform) = pick_cam.iter().last() else {
+        return;
+    };
+    let (_, gizmo_rotation, gizmo_translation) = gizmo_transform.to_scale_rotation_translation();
+    let Some(to_camera) = (gizmo_rotation.inverse()
+        * (camera_transform.translation() - gizmo_translation))
+        .try_normalize()
+    else {
+        return;
+    };
+
+    for (entity, interaction, mut transform, rest) in handles.iter_mut() {
+        let Some(HandleRest(rest)) = rest else {
+            commands.entity(entity).insert(HandleRest(*transform));
+            continue;
+        };
+        let flipped = match *interaction {
+            TransformGizmoInteraction::TranslateAxis { original, .. } => {
+                if to_camera.dot(original) < 0.0 {
+                    // half a turn around any perpendicular axis points the arrow the other way
+                    let half_turn = Quat::from_axis_angle(
+                        original.any_orthonormal_vector(),
+                        std::f32::consts::PI,
+                    );
+                    Transform::from_rotation(half_turn) * *rest
+                } else {
+                    *rest
+                }
+            }
+            TransformGizmoInteraction::TranslatePlane { original, normal }
+                if original != Vec3::ZERO =>
+            {
+                // move the quad to the quadrant of the plane on the camera side
+                let signs = Vec3::select(
+                    normal.abs().cmpgt(Vec3::splat(0.5)),
+                    Vec3::ONE,
+                    to_camera.signum(),
+                );
+                Transform {
+                    translation: rest.translation * signs,
+                    ..*rest
+                }
+            }
+            _ => continue,
+        };
+        if *transform != flipped {
+            *transform = flipped;
+        }
+    }
+}
+
+/// Fade out plane handles seen edge-on and stop picking translation handles that are faded out,
+/// arrows fade in the shader.
+pub(crate) fn fade_handles(
+    mut commands: Commands,
+    gizmo: Query<(&GlobalTransform, &TransformGizmo)>,
+    pick_cam: Query<&GlobalTransform, With<GizmoPickSource>>,
+    handles: Query<
+        (
+            Entity,
+            &TransformGizmoInteraction,
+            &GlobalTransform,
+            Has<Pickable>,
+        ),
+        Without<TransformGizmo>,
+    >,
+    handle_materials: Query<&MeshMaterial3d<GizmoMaterial>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+) {
+    let Ok((gizmo_transform, gizmo)) = gizmo.single() else {
+        return;
+    };
+    let Some(camera_transform) = pick_cam.iter().last() else {
+        return;
+    };
+    let gizmo_rotation = gizmo_transform.rotation();
+
+    for (entity, interaction, transform, pickable) in handles.iter() {
+        let Some(to_camera) =
+            (camera_transform.translation() - transform.translation()).try_normalize()
+        else {
+            continue;
+        };
+        let fade = handle_fade(interaction, gizmo_rotation, to_camera);
+
+        if let TransformGizmoInteraction::TranslatePlane { original, .. } = interaction
+            && *original != Vec3::ZERO
+            && let Ok(material) = handle_materials.get(entity)
+            && materials
+                .get(material)
+                .is_some_and(|m| (m.color.alpha - fade).abs() > 0.01)
+            && let Some(material) = materials.get_mut(material)
+        {
+            material.color.alpha = fade;
+        }
+
+        // keep the dragged handle pickable, the drag events stop when it is ignored
+        if gizmo.current_interaction.is_some() {
+            continue;
+        }
+        match (fade < MIN_PICKABLE_FADE, pickable) {
+            (true, false) => {
+                commands.entity(entity).insert(Pickable::IGNORE);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Pickable>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Turn the view rotation ring and the trackball disc towards the camera.
+pub(crate) fn face_view_handles(
+    gizmo: Query<&GlobalTransform, With<TransformGizmo>>,
+    pick_cam: Query<&GlobalTransform, With<GizmoPickSource>>,
+    mut handles: Query<(&TransformGizmoInteraction, &mut Transform), Without<TransformGizmo>>,
+) {
+    let Ok(gizmo_transform) = gizmo.single() else {
+        return;
+    };
+    let Some(camera_transform) = pick_cam.iter().last() else {
+        return;
+    };
+    let (_, gizmo_rotation, gizmo_translation) = gizmo_transform.to_scale_rotation_translation();
+    let camera_offset =
+        gizmo_rotation.inverse() * (camera_transform.translation() - gizmo_translation);
+    let Some(to_camera) = camera_offset.try_normalize() else {
+        return;
+    };
+    let camera_distance = camera_offset.length();
+
+    for (interaction, mut transform) in handles.iter_mut() {
+        let faced = match interaction {
+            // the ring is built around its local Y
+            TransformGizmoInteraction::RotateView => {
+                Transform::from_rotation(Quat::from_rotation_arc(Vec3::Y, to_camera))
+            }
+            // the disc faces its local Z, scaled up to cover the same part of the screen as it
+            // would at the gizmo center
+            TransformGizmoInteraction::Trackball => Transform {
+                translation: -to_camera * TRACKBALL_DEPTH,
+                rotation: Quat::from_rotation_arc(Vec3::Z, to_camera),
+                scale: Vec3::splat((camera_distance + TRACKBALL_DEPTH) / camera_distance),
+            },
+            _ => continue,
+        };
+        if faced.is_finite() && *transform != faced {
+            *transform = faced;
+        }
+    }
+}
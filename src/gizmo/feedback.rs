@@ -0,0 +1,229 @@
+use bevy::{
+    asset::RenderAssetUsages, light::NotShadowCaster, mesh::Indices, prelude::*,
+    render::render_resource::PrimitiveTopology, window::PrimaryWindow,
+};
+
+use crate::{
+    gizmo::{
+        GizmoLineWidth, GizmoPickSource, GizmoRenderModeTarget, GizmoSettings, TransformGizmo,
+    },
+    gizmo_material::GizmoMaterial,
+    mesh::{GIZMO_ARC_RADIUS, line::LineStrip},
+};
+
+/// Angle swept by one triangle of the pie slice.
+const PIE_STEP: f32 = std::f32::consts::TAU / 96.0;
+
+/// State of a rotation drag, shown as a pie slice around the rotation axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RotationDrag {
+    pub center: Vec3,
+    /// World space rotation axis.
+    pub axis: Vec3,
+    /// Direction from the center to where the drag started, perpendicular to `axis`.
+    pub start: Vec3,
+    /// Signed angle rotated around `axis` so far.
+    pub angle: f32,
+}
+
+/// Filled slice from the start of a rotation drag to the current angle, with a tick at the
+/// start as a child.
+#[derive(Component)]
+pub(crate) struct RotationPie;
+
+/// Text showing the angle of the current rotation drag.
+#[derive(Component)]
+pub(crate) struct RotationLabel;
+
+/// Dot on the point a translation drag snaps to.
+#[derive(Component)]
+pub(crate) struct SnapMarker;
+
+pub(crate) fn spawn_rotation_feedback(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+    settings: Res<GizmoSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (layers, x_ray) = settings.render_mode.layers_and_x_ray();
+    let layers = (layers, GizmoRenderModeTarget);
+    let color = Color::srgb(1.0, 0.85, 0.3);
+    let tick_width = GizmoLineWidth(3.0);
+    let scale_factor = windows.single().map_or(1.0, Window::scale_factor);
+
+    commands
+        .spawn((
+            Name::from("Rotation Pie"),
+            Mesh3d(meshes.add(pie_mesh(0.0))),
+            MeshMaterial3d(materials.add(GizmoMaterial {
+                shading: 0.0,
+                ..GizmoMaterial::from(color.with_alpha(0.25)).with_x_ray(x_ray)
+            })),
+            Transform::default(),
+            Visibility::Hidden,
+            NotShadowCaster,
+            Pickable::IGNORE,
+            layers.clone(),
+            RotationPie,
+        ))
+        .with_children(|pie| {
+            pie.spawn((
+                Mesh3d(meshes.add(LineStrip::segment(
+                    Vec3::X * GIZMO_ARC_RADIUS * 0.8,
+                    Vec3::X * GIZMO_ARC_RADIUS * 1.2,
+                ))),
+                MeshMaterial3d(materials.add(
+                    GizmoMaterial::line(color, tick_width.physical(scale_factor)).with_x_ray(x_ray),
+                )),
+                tick_width,
+                NotShadowCaster,
+                Pickable::IGNORE,
+                layers,
+            ));
+        });
+
+    commands.spawn((
+        Name::from("Rotation Label"),
+        Text::new(""),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+        Node {
+            position_type: PositionType::Absolute,
+            display: Display::None,
+            ..default()
+        },
+        Pickable::IGNORE,
+        RotationLabel,
+    ));
+}
+
+pub(crate) fn spawn_snap_marker(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+    settings: Res<GizmoSettings>,
+) {
+    let (layers, x_ray) = settings.render_mode.layers_and_x_ray();
+    let layers = (layers, GizmoRenderModeTarget);
+    commands.spawn((
+        Name::from("Snap Marker"),
+        Mesh3d(meshes.add(Sphere { radius: 0.06 })),
+        MeshMaterial3d(materials.add(GizmoMaterial {
+            shading: 0.0,
+            ..GizmoMaterial::from(Color::srgb(1.0, 0.85, 0.3)).with_x_ray(x_ray)
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        NotShadowCaster,
+        Pickable::IGNORE,
+        layers,
+        SnapMarker,
+    ));
+}
+
+/// Triangle fan of `angle` radians around local Y starting on local X, the same direction as
+/// `Quat::from_rotation_y`.
+fn pie_mesh(angle: f32) -> Mesh {
+    let steps = ((angle.abs() / PIE_STEP).ceil() as usize).max(1);
+    let mut positions: Vec<[f32; 3]> = vec![[0.0; 3]];
+    let mut indices: Vec<u32> = Vec::with_capacity(steps * 3);
+    for step in 0..=steps {
+        let theta = angle * step as f32 / steps as f32;
+        positions.push((Quat::from_rotation_y(theta) * Vec3::X * GIZMO_ARC_RADIUS).into());
+        if step > 0 {
+            indices.extend_from_slice(&[0, step as u32, step as u32 + 1]);
+        }
+    }
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let uvs = vec![[0.0, 0.0]; positions.len()];
+
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    mesh.insert_indices(Indices::U32(indices));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh
+}
+
+/// Rebuild the pie slice and move the angle label while a rotation is dragged.
+pub(crate) fn update_rotation_feedback(
+    mut commands: Commands,
+    gizmo: Query<&TransformGizmo, Changed<TransformGizmo>>,
+    pick_cam: Query<(Entity, &Camera, &GlobalTransform), With<GizmoPickSource>>,
+    mut pie: Query<(&Mesh3d, &mut Transform, &mut Visibility), With<RotationPie>>,
+    // shown and hidden with `Node::display`, so it doesn't borrow `Visibility` with the pie
+    mut label: Query<(Entity, &mut Text, &mut Node, Option<&UiTargetCamera>), With<RotationLabel>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(gizmo) = gizmo.single() else {
+        return;
+    };
+    let (
+        Ok((mesh, mut pie_transform, mut pie_visibility)),
+        Ok((label, mut text, mut node, target)),
+    ) = (pie.single_mut(), label.single_mut())
+    else {
+        return;
+    };
+
+    let Some(drag) = gizmo.rotation_drag else {
+        *pie_visibility = Visibility::Hidden;
+        node.display = Display::None;
+        return;
+    };
+
+    let Some(normal) = drag.start.cross(drag.axis).try_normalize() else {
+        return;
+    };
+    // local X is the start direction and local Y the rotation axis
+    pie_transform.translation = drag.center;
+    pie_transform.rotation = Quat::from_mat3(&Mat3::from_cols(drag.start, drag.axis, normal));
+    if let Some(mesh) = meshes.get_mut(mesh) {
+        *mesh = pie_mesh(drag.angle);
+    }
+    *pie_visibility = Visibility::Inherited;
+
+    text.0 = format!("{:+.1}°", drag.angle.to_degrees());
+    let label_position = drag.center
+        + Quat::from_axis_angle(drag.axis, drag.angle * 0.5) * drag.start * GIZMO_ARC_RADIUS * 1.3;
+    let Some((camera_entity, camera, camera_transform)) = pick_cam.iter().last() else {
+        return;
+    };
+    match camera.world_to_viewport(camera_transform, label_position) {
+        Ok(position) => {
+            node.left = Val::Px(position.x);
+            node.top = Val::Px(position.y);
+            node.display = Display::Flex;
+            // only when the pick camera changed, every insert marks the label changed for the UI
+            if target.map(UiTargetCamera::entity) != Some(camera_entity) {
+                commands.entity(label).insert(UiTargetCamera(camera_entity));
+            }
+        }
+        Err(_) => node.display = Display::None,
+    }
+}
+
+/// Show the snap marker on the point the current drag snaps to.
+pub(crate) fn update_snap_marker(
+    gizmo: Query<&TransformGizmo, Changed<TransformGizmo>>,
+    mut marker: Query<(&mut Transform, &mut Visibility), With<SnapMarker>>,
+) {
+    let (Ok(gizmo), Ok((mut transform, mut visibility))) = (gizmo.single(), marker.single_mut())
+    else {
+        return;
+    };
+    match gizmo.snap_target {
+        Some(target) => {
+            transform.translation = target;
+            *visibility = Visibility::Inherited;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::gizmo::drag_math::write_finite;
+
+/// Edits kept before the oldest is forgotten.
+const HISTORY_LIMIT: usize = 100;
+
+/// Transform of one entity before and after an edit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TransformChange {
+    pub entity: Entity,
+    pub before: Transform,
+    pub after: Transform,
+}
+
+/// Changes made together that are undone together.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformEdit {
+    /// What made the edit, for logging.
+    pub name: &'static str,
+    pub changes: Vec<TransformChange>,
+}
+
+impl TransformEdit {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Add a change unless `before` and `after` are the same.
+    pub fn push(&mut self, entity: Entity, before: Transform, after: Transform) {
+        if before != after {
+            self.changes.push(TransformChange {
+                entity,
+                before,
+                after,
+            });
+        }
+    }
+}
+
+/// Transform edits made by the gizmo and its commands that [`UndoTransform`] and
+/// [`RedoTransform`] step through.
+#[derive(Resource, Debug, Default)]
+pub struct TransformHistory {
+    undo: Vec<TransformEdit>,
+    redo: Vec<TransformEdit>,
+}
+
+impl TransformHistory {
+    /// Remember an edit that was just applied, forgetting everything that was undone.
+    pub fn record(&mut self, edit: TransformEdit) {
+        if edit.changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(edit);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Drop every change of `entity`, for when its transform no longer means what it did when
+    /// they were recorded. Edits left without changes are dropped too.
+    pub fn forget(&mut self, entity: Entity) {
+        for edits in [&mut self.undo, &mut self.redo] {
+            for edit in edits.iter_mut() {
+                edit.changes.retain(|change| change.entity != entity);
+            }
+            edits.retain(|edit| !edit.changes.is_empty());
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Put back the transforms from before the last recorded edit.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct UndoTransform;
+
+/// Apply the last undone edit again.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct RedoTransform;
+
+/// Ctrl+Z undoes, Ctrl+Shift+Z and Ctrl+Y redo.
+pub(crate) fn history_keys(mut commands: Commands, keyboard: Res<ButtonInput<KeyCode>>) {
+    if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keyboard.just_pressed(KeyCode::KeyZ) && !shift {
+        commands.trigger(UndoTransform);
+    } else if keyboard.just_pressed(KeyCode::KeyY) || keyboard.just_pressed(KeyCode::KeyZ) {
+        commands.trigger(RedoTransform);
+    }
+}
+
+pub(crate) fn undo(
+    _undo: On<UndoTransform>,
+    mut history: ResMut<TransformHistory>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(edit) = history.undo.pop() else {
+        info!("nothing to undo");
+        return;
+    };
+    for change in edit.changes.iter().rev() {
+        if let Ok(mut transform) = transforms.get_mut(change.entity) {
+            write_finite(&mut transform, change.before);
+        }
+    }
+    info!("undo {}", edit.name);
+    history.redo.push(edit);
+}
+
+pub(crate) fn redo(
+    _redo: On<RedoTransform>,
+    mut history: ResMut<TransformHistory>,
+    mut transforms: Query<&mut Transform>,
+) {
+    let Some(edit) = history.redo.pop() else {
+        info!("nothing to redo");
+        return;
+    };
+    for change in &edit.changes {
+        if let Ok(mut transform) = transforms.get_mut(change.entity) {
+            write_finite(&mut transform, change.after);
+        }
+    }
+    info!("redo {}", edit.name);
+    history.undo.push(edit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forgotten_entities_leave_the_history() {
+        let (a, b) = (Entity::from_bits(1), Entity::from_bits(2));
+        let moved = Transform::from_xyz(1.0, 0.0, 0.0);
+        let mut history = TransformHistory::default();
+        let mut both = TransformEdit::new("both");
+        both.push(a, Transform::IDENTITY, moved);
+        both.push(b, Transform::IDENTITY, moved);
+        history.record(both);
+        let mut only_a = TransformEdit::new("a");
+        only_a.push(a, moved, Transform::IDENTITY);
+        history.record(only_a);
+
+        history.forget(a);
+        assert_eq!(history.undo.len(), 1);
+        assert_eq!(history.undo[0].changes.len(), 1);
+        assert_eq!(history.undo[0].changes[0].entity, b);
+    }
+}
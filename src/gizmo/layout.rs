@@ -0,0 +1,350 @@
+use bevy::{camera::primitives::Aabb, ecs::system::SystemParam, prelude::*};
+
+use crate::gizmo::{
+    PickSelection, TransformGizmo,
+    drag_math::write_finite,
+    history::{TransformEdit, TransformHistory},
+};
+
+/// World axis the selection is laid out along.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl LayoutAxis {
+    fn index(self) -> usize {
+        match self {
+            LayoutAxis::X => 0,
+            LayoutAxis::Y => 1,
+            LayoutAxis::Z => 2,
+        }
+    }
+
+    pub(crate) fn unit(self) -> Vec3 {
+        Vec3::AXES[self.index()]
+    }
+}
+
+/// Part of the bounds that is lined up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlignSide {
+    Min,
+    Center,
+    Max,
+}
+
+impl AlignSide {
+    fn of(self, (min, max): (f32, f32)) -> f32 {
+        match self {
+            AlignSide::Min => min,
+            AlignSide::Center => (min + max) * 0.5,
+            AlignSide::Max => max,
+        }
+    }
+}
+
+/// What the selection is lined up with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlignTarget {
+    /// The bounds of one entity, usually the active element of the selection. It is not moved.
+    Entity(Entity),
+    /// The bounds around the whole selection.
+    Selection,
+}
+
+/// Move every selected entity along `axis` so the `side` of its bounds lines up with the same
+/// side of `target`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AlignSelection {
+    pub axis: LayoutAxis,
+    pub side: AlignSide,
+    pub target: AlignTarget,
+}
+
+/// Move the selected entities along `axis` so the gaps between their bounds are equal, keeping
+/// the first and the last where they are.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DistributeSelection {
+    pub axis: LayoutAxis,
+}
+
+/// Offsets that leave equal gaps between `ranges` of `(min, max)`, in the order of their centers
+/// and without moving the first and the last.
+fn distribute_offsets(ranges: &[(f32, f32)]) -> Vec<f32> {
+    let mut offsets = vec![0.0; ranges.len()];
+    if ranges.len() < 3 {
+        return offsets;
+    }
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by(|&a, &b| {
+        let center = |(min, max): (f32, f32)| min + max;
+        center(ranges[a]).total_cmp(&center(ranges[b]))
+    });
+    let (first, last) = (ranges[order[0]], ranges[order[order.len() - 1]]);
+    let sizes: f32 = ranges.iter().map(|(min, max)| max - min).sum();
+    let gap = (last.1 - first.0 - sizes) / (ranges.len() - 1) as f32;
+    let mut cursor = first.0;
+    for index in order {
+        let (min, max) = ranges[index];
+        offsets[index] = cursor - min;
+        cursor += max - min + gap;
+    }
+    offsets
+}
+
+/// How far a box of `(min, max)` reaches from `origin` against `normal`, 0 when all of it is on
+/// the side `normal` points to.
+fn extent_below((min, max): (Vec3, Vec3), origin: Vec3, normal: Vec3) -> f32 {
+    let lowest = ((min - origin) * normal)
+        .min((max - origin) * normal)
+        .element_sum();
+    (-lowest).max(0.0)
+}
+
+/// World space bounds of entities, read from the [`Aabb`] of their meshes.
+#[derive(SystemParam)]
+pub(crate) struct EntityBounds<'w, 's> {
+    children: Query<'w, 's, &'static Children>,
+    meshes: Query<'w, 's, (&'static Aabb, &'static GlobalTransform)>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+impl EntityBounds<'_, '_> {
+    /// Box around the meshes of `entity` and its descendants as `(min, max)`, or just its origin
+    /// when it has no mesh.
+    pub fn get(&self, entity: Entity) -> Option<(Vec3, Vec3)> {
+        let (mut min, mut max) = (Vec3::INFINITY, Vec3::NEG_INFINITY);
+        let entities = std::iter::once(entity).chain(self.children.iter_descendants(entity));
+        for (aabb, transform) in entities.filter_map(|entity| self.meshes.get(entity).ok()) {
+            let (local_min, local_max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+            for corner in 0..8 {
+                let pick = BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0);
+                let point = transform.transform_point(Vec3::select(pick, local_max, local_min));
+                min = min.min(point);
+                max = max.max(point);
+            }
+        }
+        if min.cmple(max).all() {
+            return Some((min, max));
+        }
+        let origin = self.transforms.get(entity).ok()?.translation();
+        Some((origin, origin))
+    }
+
+    /// How far the bounds of `entities` reach from `origin` against the unit `normal`, so
+    /// moving `origin` that far along `normal` from a surface puts them on top of it.
+    pub fn extent_below(
+        &self,
+        entities: impl IntoIterator<Item = Entity>,
+        origin: Vec3,
+        normal: Vec3,
+    ) -> f32 {
+        entities
+            .into_iter()
+            .filter_map(|entity| self.get(entity))
+            .map(|bounds| extent_below(bounds, origin, normal))
+            .fold(0.0, f32::max)
+    }
+
+    /// `(min, max)` of the bounds of `entity` along `axis`.
+    fn range(&self, entity: Entity, axis: LayoutAxis) -> Option<(f32, f32)> {
+        let (min, max) = self.get(entity)?;
+        Some((min[axis.index()], max[axis.index()]))
+    }
+
+    /// World space `offset` in the space of `parent`, where the translation of its child is.
+    fn local_offset(&self, parent: Option<&ChildOf>, offset: Vec3) -> Vec3 {
+        match parent.and_then(|parent| self.transforms.get(parent.parent()).ok()) {
+            Some(parent) => parent.affine().inverse().transform_vector3(offset),
+            None => offset,
+        }
+    }
+}
+
+type LayoutQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static PickSelection,
+        Option<&'static ChildOf>,
+    ),
+    Without<TransformGizmo>,
+>;
+
+/// Selected entities without a selected ancestor, the others move along with their parent.
+fn selection_roots(items: &LayoutQuery, parents: &Query<&ChildOf>) -> Vec<Entity> {
+    let selected = |entity: Entity| {
+        items
+            .get(entity)
+            .is_ok_and(|(_, _, pick, _)| pick.is_selected)
+    };
+    items
+        .iter()
+        .filter(|(entity, _, pick, _)| {
+            pick.is_selected && !parents.iter_ancestors(*entity).any(selected)
+        })
+        .map(|(entity, ..)| entity)
+        .collect()
+}
+
+/// Move each selected entity along `axis` by its offset and record the moves as one edit.
+fn move_selection(
+    name: &'static str,
+    axis: LayoutAxis,
+    offsets: &[(Entity, f32)],
+    bounds: &EntityBounds,
+    items: &mut LayoutQuery,
+    history: &mut TransformHistory,
+) {
+    let mut edit = TransformEdit::new(name);
+    for &(entity, offset) in offsets {
+        let Ok((_, mut transform, _, parent)) = items.get_mut(entity) else {
+            continue;
+        };
+        let before = *transform;
+        let moved = Transform {
+            translation: before.translation + bounds.local_offset(parent, axis.unit() * offset),
+            ..before
+        };
+        if write_finite(&mut transform, moved) {
+            edit.push(entity, before, moved);
+        }
+    }
+    history.record(edit);
+}
+
+pub(crate) fn align_selection(
+    align: On<AlignSelection>,
+    bounds: EntityBounds,
+    mut items: LayoutQuery,
+    parents: Query<&ChildOf>,
+    mut history: ResMut<TransformHistory>,
+) {
+    let ranges: Vec<(Entity, (f32, f32))> = selection_roots(&items, &parents)
+        .into_iter()
+        .filter_map(|entity| Some((entity, bounds.range(entity, align.axis)?)))
+        .collect();
+    let target = match align.target {
+        AlignTarget::Entity(entity) => bounds.range(entity, align.axis),
+        AlignTarget::Selection => ranges
+            .iter()
+            .map(|(_, range)| *range)
+            .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b))),
+    };
+    let Some(target) = target.map(|range| align.side.of(range)) else {
+        warn!("nothing to align to");
+        return;
+    };
+    let offsets: Vec<(Entity, f32)> = ranges
+        .iter()
+        .map(|(entity, range)| (*entity, target - align.side.of(*range)))
+        .collect();
+    move_selection(
+        "align",
+        align.axis,
+        &offsets,
+        &bounds,
+        &mut items,
+        &mut history,
+    );
+}
+
+pub(crate) fn distribute_selection(
+    distribute: On<DistributeSelection>,
+    bounds: EntityBounds,
+    mut items: LayoutQuery,
+    parents: Query<&ChildOf>,
+    mut history: ResMut<TransformHistory>,
+) {
+    let (entities, ranges): (Vec<Entity>, Vec<(f32, f32)>) = selection_roots(&items, &parents)
+        .into_iter()
+        .filter_map(|entity| Some((entity, bounds.range(entity, distribute.axis)?)))
+        .unzip();
+    let offsets: Vec<(Entity, f32)> = entities
+        .into_iter()
+        .zip(distribute_offsets(&ranges))
+        .collect();
+    move_selection(
+        "distribute",
+        distribute.axis,
+        &offsets,
+        &bounds,
+        &mut items,
+        &mut history,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distribute_leaves_equal_gaps() {
+        // sizes 1, 2 and 1 between 0 and 10, given out of order
+        let ranges = [(9.0, 10.0), (0.0, 1.0), (2.0, 4.0)];
+        let offsets = distribute_offsets(&ranges);
+        assert_eq!(offsets, vec![0.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn children_move_with_their_selected_parent() {
+        let mut world = World::new();
+        world.init_resource::<TransformHistory>();
+        world.add_observer(align_selection);
+        let selected = PickSelection {
+            is_selected: true,
+            ..default()
+        };
+        let at = |x: f32| {
+            let transform = Transform::from_xyz(x, 0.0, 0.0);
+            (transform, GlobalTransform::from(transform))
+        };
+        let parent = world.spawn((at(2.0), selected.clone())).id();
+        // one to the right of its parent in both spaces
+        let child = world
+            .spawn((
+                Transform::from_xyz(1.0, 0.0, 0.0),
+                GlobalTransform::from_xyz(3.0, 0.0, 0.0),
+                selected.clone(),
+                ChildOf(parent),
+            ))
+            .id();
+        let other = world.spawn((at(5.0), selected)).id();
+
+        world.trigger(AlignSelection {
+            axis: LayoutAxis::X,
+            side: AlignSide::Min,
+            target: AlignTarget::Selection,
+        });
+        let x = |world: &World, entity| world.get::<Transform>(entity).unwrap().translation.x;
+        assert_eq!(x(&world, parent), 2.0);
+        assert_eq!(x(&world, child), 1.0);
+        assert_eq!(x(&world, other), 2.0);
+    }
+
+    #[test]
+    fn extent_below_reaches_the_lowest_corner() {
+        let bounds = (Vec3::new(-1.0, -0.5, -1.0), Vec3::new(1.0, 1.5, 1.0));
+        assert_eq!(extent_below(bounds, Vec3::ZERO, Vec3::Y), 0.5);
+        assert_eq!(extent_below(bounds, Vec3::ZERO, Vec3::NEG_Y), 1.5);
+        let tilted = Vec3::new(1.0, 1.0, 0.0).normalize();
+        assert!((extent_below(bounds, Vec3::ZERO, tilted) - 1.5 / 2f32.sqrt()).abs() < 1e-5);
+        // a box entirely above the origin doesn't lift it
+        let above = (Vec3::new(-1.0, 1.0, -1.0), Vec3::ONE * 2.0);
+        assert_eq!(extent_below(above, Vec3::ZERO, Vec3::Y), 0.0);
+    }
+
+    #[test]
+    fn distribute_needs_three() {
+        assert_eq!(
+            distribute_offsets(&[(0.0, 1.0), (5.0, 6.0)]),
+            vec![0.0, 0.0]
+        );
+        assert!(distribute_offsets(&[]).is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use bevy::{math::Affine3A, prelude::*};
+
+use crate::gizmo::{
+    PickSelection, TransformGizmo,
+    drag_math::write_finite,
+    history::{TransformEdit, TransformHistory},
+    layout::LayoutAxis,
+};
+
+/// Plane a [`Mirrored`] entity is reflected across.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct MirrorPlane {
+    pub normal: Vec3,
+    pub origin: Vec3,
+    /// Entity the plane is given in the local space of and moves with, world space when `None`.
+    pub relative_to: Option<Entity>,
+}
+
+impl Default for MirrorPlane {
+    fn default() -> Self {
+        Self {
+            normal: Vec3::X,
+            origin: Vec3::ZERO,
+            relative_to: None,
+        }
+    }
+}
+
+impl MirrorPlane {
+    /// World plane through `origin` perpendicular to `axis`.
+    pub fn world(axis: LayoutAxis, origin: Vec3) -> Self {
+        Self {
+            normal: axis.unit(),
+            origin,
+            relative_to: None,
+        }
+    }
+}
+
+/// Keeps this entity the mirror image of `source` across `plane`, following every edit of the
+/// source.
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+#[relationship(relationship_target = MirroredBy)]
+pub struct Mirrored {
+    #[relationship]
+    pub source: Entity,
+    pub plane: MirrorPlane,
+}
+
+/// Entities that are kept the [`Mirrored`] image of this one.
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+#[relationship_target(relationship = Mirrored)]
+pub struct MirroredBy(Vec<Entity>);
+
+/// Mirror the selected entities across the world plane perpendicular to `axis` through the
+/// gizmo.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MirrorSelection {
+    pub axis: LayoutAxis,
+    /// Leave the selection where it is and spawn [`Mirrored`] copies that follow it instead.
+    pub linked_copies: bool,
+}
+
+/// `transform` reflected across the plane through `origin` with `normal`.
+///
+/// A reflection can't be written as a rotation, so the local axis closest to the normal gets a
+/// negative scale.
+pub fn mirror_transform(transform: &Transform, normal: Vec3, origin: Vec3) -> Option<Transform> {
+    let normal = normal.try_normalize()?;
+    let outer = Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+    let reflect = Mat3::IDENTITY - 2.0 * outer;
+    let local_normal = (transform.rotation.inverse() * normal).abs();
+    let flipped = if local_normal.x >= local_normal.y && local_normal.x >= local_normal.z {
+        Vec3::new(-1.0, 1.0, 1.0)
+    } else if local_normal.y >= local_normal.z {
+        Vec3::new(1.0, -1.0, 1.0)
+    } else {
+        Vec3::new(1.0, 1.0, -1.0)
+    };
+    let rotation = reflect * Mat3::from_quat(transform.rotation) * Mat3::from_diagonal(flipped);
+    let mirrored = Transform {
+        translation: transform.translation
+            - 2.0 * (transform.translation - origin).dot(normal) * normal,
+        rotation: Quat::from_mat3(&rotation).normalize(),
+        scale: transform.scale * flipped,
+    };
+    mirrored.is_finite().then_some(mirrored)
+}
+
+/// Plane through `origin` with `normal` moved by `affine`. The normal goes through the inverse
+/// transpose so it stays perpendicular to the plane under non-uniform scale.
+fn transform_plane(affine: Affine3A, normal: Vec3, origin: Vec3) -> (Vec3, Vec3) {
+    let normal_matrix = Mat3::from(affine.matrix3).inverse().transpose();
+    (normal_matrix * normal, affine.transform_point3(origin))
+}
+
+/// `local`, the transform of a child of `source_space`, reflected across the world plane through
+/// `origin` with `normal` and written in `target_space`, world space for `None`.
+///
+/// The reflection is done in world space, in a non-uniformly scaled parent space it would be
+/// slanted. `None` when the plane is degenerate or the result would need a shear, which a
+/// [`Transform`] can't hold.
+fn mirror_in_world(
+    local: &Transform,
+    source_space: Option<&GlobalTransform>,
+    target_space: Option<&GlobalTransform>,
+    normal: Vec3,
+    origin: Vec3,
+) -> Option<Transform> {
+    let world = match source_space {
+        Some(space) => space.mul_transform(*local),
+        None => GlobalTransform::from(*local),
+    };
+    let normal = normal.try_normalize()?;
+    let outer = Mat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+    let reflect = Affine3A::from_mat3_translation(
+        Mat3::IDENTITY - 2.0 * outer,
+        2.0 * origin.dot(normal) * normal,
+    );
+    let target = target_space.map_or(Affine3A::IDENTITY, GlobalTransform::affine);
+    let exact = target.inverse() * reflect * world.affine();
+
+    // mirroring the world transform picks the nicer axis to flip, but loses a shear the world
+    // transform may already have that the parent takes away again
+    let mirrored = mirror_transform(&world.compute_transform(), normal, origin).map(|mirrored| {
+        match target_space {
+            Some(space) => GlobalTransform::from(mirrored).reparented_to(space),
+            None => mirrored,
+        }
+    });
+    let decomposed = Transform::from_matrix(Mat4::from(exact));
+    let exact = exact.to_cols_array();
+    let tolerance = 1e-4
+        * exact
+            .iter()
+            .fold(1.0, |max: f32, value| max.max(value.abs()));
+    let matches = |transform: &Transform| {
+        transform.is_finite()
+            && (exact.iter().zip(transform.compute_affine().to_cols_array()))
+                .all(|(exact, actual)| (exact - actual).abs() <= tolerance)
+    };
+    mirrored.into_iter().chain([decomposed]).find(matches)
+}
+
+/// Move every [`Mirrored`] entity whose source or plane changed to the mirror image of its
+/// source.
+pub(crate) fn follow_mirrors(
+    mut mirrors: Query<(Ref<Mirrored>, &mut Transform, Option<&ChildOf>)>,
+    sources: Query<(Ref<Transform>, Option<&ChildOf>), Without<Mirrored>>,
+    spaces: Query<Ref<GlobalTransform>>,
+) {
+    for (mirrored, mut transform, mirror_parent) in mirrors.iter_mut() {
+        let Ok((source, source_parent)) = sources.get(mirrored.source) else {
+            continue;
+        };
+        let space = |parent: Option<&ChildOf>| parent.and_then(|p| spaces.get(p.parent()).ok());
+        let (mirror_space, source_space) = (space(mirror_parent), space(source_parent));
+        let plane = mirrored.plane;
+        let (normal, origin, plane_changed) = match plane.relative_to {
+            Some(entity) => {
+                let Ok(space) = spaces.get(entity) else {
+                    continue;
+                };
+                let (normal, origin) = transform_plane(space.affine(), plane.normal, plane.origin);
+                (normal, origin, space.is_changed())
+            }
+            None => (plane.normal, plane.origin, false),
+        };
+        let parents_changed = [&mirror_space, &source_space]
+            .into_iter()
+            .any(|space| space.as_ref().is_some_and(|space| space.is_changed()));
+        if !source.is_changed() && !mirrored.is_changed() && !plane_changed && !parents_changed {
+            continue;
+        }
+        let (source_space, mirror_space) = (source_space.as_deref(), mirror_space.as_deref());
+        match mirror_in_world(&source, source_space, mirror_space, normal, origin) {
+            Some(mirror) => {
+                if *transform != mirror {
+                    *transform = mirror;
+                }
+            }
+            None => {
+                let source = mirrored.source;
+                warn!("can't mirror {source} across {plane:?} without shearing it");
+            }
+        }
+    }
+}
+
+type MirrorQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static PickSelection,
+        Option<&'static ChildOf>,
+    ),
+    Without<TransformGizmo>,
+>;
+
+pub(crate) fn mirror_selection(
+    mirror: On<MirrorSelection>,
+    mut commands: Commands,
+    gizmo: Query<&Transform, With<TransformGizmo>>,
+    mut items: MirrorQuery,
+    spaces: Query<&GlobalTransform>,
+    mut history: ResMut<TransformHistory>,
+) {
+    let Ok(gizmo) = gizmo.single() else {
+        warn!("getting main gizmo error");
+        return;
+    };
+    let plane = MirrorPlane::world(mirror.axis, gizmo.translation);
+
+    if mirror.linked_copies {
+        for (source, ..) in items.iter().filter(|(_, _, pick, _)| pick.is_selected) {
+            commands
+                .entity(source)
+                .clone_and_spawn_with_opt_out(|builder| {
+                    builder.linked_cloning(true).add_observers(true);
+                })
+                .insert((PickSelection::default(), Mirrored { source, plane }));
+        }
+        return;
+    }
+
+    let mut edit = TransformEdit::new("mirror");
+    let selected = items.iter_mut().filter(|(_, _, pick, _)| pick.is_selected);
+    for (entity, mut transform, _, parent) in selected {
+        let parent = parent.and_then(|parent| spaces.get(parent.parent()).ok());
+        let Some(mirrored) =
+            mirror_in_world(&transform, parent, parent, plane.normal, plane.origin)
+        else {
+            warn!("can't mirror {entity} in the space of its parent without shearing it");
+            continue;
+        };
+        let before = *transform;
+        if write_finite(&mut transform, mirrored) {
+            edit.push(entity, before, mirrored);
+        }
+    }
+    history.record(edit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mirrored_points_are_reflected() {
+        let transform = Transform::from_xyz(3.0, 1.0, -2.0)
+            .with_rotation(Quat::from_euler(EulerRot::YXZ, 0.4, -0.3, 1.1))
+            .with_scale(Vec3::new(1.0, 2.0, 0.5));
+        let (normal, origin) = (
+            Vec3::new(1.0, 1.0, 0.0).normalize(),
+            Vec3::new(0.5, 0.0, 0.0),
+        );
+        let mirrored = mirror_transform(&transform, normal, origin).unwrap();
+        let point = Vec3::new(0.3, -0.7, 1.2);
+        let world = transform.transform_point(point);
+        let reflected = world - 2.0 * (world - origin).dot(normal) * normal;
+        assert!(mirrored.transform_point(point).abs_diff_eq(reflected, 1e-4));
+    }
+
+    #[test]
+    fn mirroring_twice_gives_the_original() {
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0)
+            .with_rotation(Quat::from_rotation_z(0.5) * Quat::from_rotation_x(0.2));
+        let once = mirror_transform(&transform, Vec3::X, Vec3::ZERO).unwrap();
+        let twice = mirror_transform(&once, Vec3::X, Vec3::ZERO).unwrap();
+        assert!(twice.translation.abs_diff_eq(transform.translation, 1e-5));
+        // q and -q are the same rotation
+        assert!(twice.rotation.dot(transform.rotation).abs() > 1.0 - 1e-5);
+        assert!(twice.scale.abs_diff_eq(transform.scale, 1e-5));
+        assert_eq!(mirror_transform(&transform, Vec3::ZERO, Vec3::ZERO), None);
+    }
+
+    #[test]
+    fn children_are_mirrored_in_world_space() {
+        let parent = GlobalTransform::from(
+            Transform::from_xyz(-1.0, 0.5, 0.0).with_rotation(Quat::from_rotation_y(0.7)),
+        );
+        let child = Transform::from_xyz(1.0, 0.0, 2.0);
+        let origin = Vec3::new(0.5, 0.0, 0.0);
+        let space = Some(&parent);
+        let mirrored = mirror_in_world(&child, space, space, Vec3::X, origin).unwrap();
+        let before = parent.transform_point(child.translation);
+        let after = parent.transform_point(mirrored.translation);
+        assert!(after.abs_diff_eq(Vec3::new(1.0 - before.x, before.y, before.z), 1e-5));
+
+        // on the plane it stays where it is
+        let on_plane = Transform::from_translation(
+            parent
+                .affine()
+                .inverse()
+                .transform_point3(Vec3::new(0.5, 2.0, -1.0)),
+        );
+        let mirrored = mirror_in_world(&on_plane, space, space, Vec3::X, origin).unwrap();
+        assert!(mirrored.translation.abs_diff_eq(on_plane.translation, 1e-5));
+    }
+
+    #[test]
+    fn children_of_scaled_parents_are_mirrored_in_world_space() {
+        let parent = GlobalTransform::from(
+            Transform::from_xyz(0.0, 1.0, 0.0)
+                .with_rotation(Quat::from_rotation_y(0.3))
+                .with_scale(Vec3::new(3.0, 1.0, 1.0)),
+        );
+        let (normal, origin) = (Vec3::Y, Vec3::new(0.0, 0.5, 0.0));
+        let space = Some(&parent);
+        let point = Vec3::new(0.2, -0.4, 0.7);
+        // turned inside the stretched parent the child is sheared in world space
+        let child = Transform::from_xyz(1.0, 2.0, -1.0).with_rotation(Quat::from_rotation_z(0.8));
+        let mirrored = mirror_in_world(&child, space, space, normal, origin).unwrap();
+        let world = parent.transform_point(child.transform_point(point));
+        let reflected = world - 2.0 * (world - origin).dot(normal) * normal;
+        let after = parent.transform_point(mirrored.transform_point(point));
+        assert!(after.abs_diff_eq(reflected, 1e-4));
+
+        // a plane slanted to the stretch would need a shear in the space of the parent
+        let slanted = Vec3::new(1.0, 1.0, 0.0);
+        assert_eq!(
+            mirror_in_world(&Transform::IDENTITY, space, space, slanted, origin),
+            None
+        );
+    }
+
+    #[test]
+    fn planes_keep_their_normal_under_non_uniform_scale() {
+        let affine = Affine3A::from_scale_rotation_translation(
+            Vec3::new(3.0, 1.0, 1.0),
+            Quat::IDENTITY,
+            Vec3::ZERO,
+        );
+        let (normal, origin) = transform_plane(affine, Vec3::new(1.0, 1.0, 0.0), Vec3::ZERO);
+        // the plane x + y = 0 is x / 3 + y = 0 after the scale
+        let in_plane = affine.transform_vector3(Vec3::new(1.0, -1.0, 0.0));
+        assert!(normal.dot(in_plane).abs() < 1e-5);
+        assert_eq!(origin, Vec3::ZERO);
+    }
+}
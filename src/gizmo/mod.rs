@@ -1,16 +1,160 @@
-use bevy::{picking::backend::PointerHits, prelude::*, window::PrimaryWindow};
+use bevy::{
+    camera::visibility::RenderLayers,
+    ecs::system::SystemParam,
+    picking::backend::PointerHits,
+    prelude::*,
+    window::{PrimaryWindow, WindowScaleFactorChanged},
+};
 
 use crate::{
-    gizmo::debug_vectors::{DebugVectors, DebugVectorsPlugin, RotateDebugVectors},
+    axis::grid::GridSettings,
+    gizmo::{
+        constraints::TransformConstraints,
+        debug_vectors::DebugVectors,
+        drag_math::{
+            ScreenAngle, align_axis, axis_drag_plane, intersect_plane, project_onto_plane,
+            screen_axis_distance, trackball_rotation, wrap_position, write_finite,
+        },
+        layout::EntityBounds,
+        surface::SurfaceRayCast,
+        vertex_snap::VertexSnap,
+    },
     gizmo_material::GizmoMaterial,
+    mesh::GIZMO_RENDER_LAYER,
 };
 
+pub mod align;
+pub mod array;
+pub mod bake;
+pub mod constraints;
 pub mod debug_vectors;
+pub mod drag_math;
+pub mod duplicate;
+mod facing;
+mod feedback;
+pub mod history;
+pub mod layout;
+pub mod mirror;
+mod origin;
+mod surface;
+mod vertex_snap;
 
 #[derive(Component)]
 pub struct GizmoPickSource;
 
-#[derive(Component, Debug, Default)]
+/// How the gizmo is kept visible when it is inside or behind scene geometry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum GizmoRenderMode {
+    /// Drawn on [`GIZMO_RENDER_LAYER`](crate::mesh::GIZMO_RENDER_LAYER) by a second camera
+    /// marked [`GizmoPickSource`] that has to follow the main camera, always on top.
+    #[default]
+    Overlay,
+    /// Drawn by the main camera, marked [`GizmoPickSource`], with the parts hidden behind scene
+    /// geometry dimmed and dashed. That camera needs a `DepthPrepass`.
+    XRay,
+}
+
+/// Gizmo mesh drawn according to [`GizmoSettings::render_mode`], moved to other render layers
+/// when it changes.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct GizmoRenderModeTarget;
+
+/// Width in logical pixels of the line material of a gizmo mesh, the material is given it in
+/// physical pixels of the primary window.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct GizmoLineWidth(pub f32);
+
+impl GizmoLineWidth {
+    /// Material line width for a window with `scale_factor`.
+    pub fn physical(self, scale_factor: f32) -> f32 {
+        self.0 * scale_factor
+    }
+}
+
+impl GizmoRenderMode {
+    /// Render layers of the gizmo meshes and whether their material is drawn in x-ray.
+    pub fn layers_and_x_ray(&self) -> (RenderLayers, bool) {
+        match self {
+            GizmoRenderMode::Overlay => (RenderLayers::layer(GIZMO_RENDER_LAYER), false),
+            GizmoRenderMode::XRay => (RenderLayers::default(), true),
+        }
+    }
+}
+
+/// Where a snapping translation drag moves the selection from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum SnapSource {
+    /// The pivot of the gizmo.
+    #[default]
+    Pivot,
+    /// The vertex of the selected meshes closest to the pointer when snapping starts.
+    NearestVertex,
+}
+
+/// Settings of the transform gizmo. The look is read when the gizmo is spawned, apart from
+/// `render_mode` which can be changed at any time, the drag settings while dragging.
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct GizmoSettings {
+    pub render_mode: GizmoRenderMode,
+    /// Draw the rotation handles as full circles instead of quarter arcs.
+    pub full_rotation_rings: bool,
+    /// Holding any of these keys while dragging moves the pointer by `precision_factor`.
+    pub precision_keys: Vec<KeyCode>,
+    pub precision_factor: f32,
+    /// Distance in pixels the pointer has to move before a drag changes anything, releasing
+    /// before that is a click that triggers [`GizmoHandleClicked`].
+    pub drag_threshold: f32,
+    /// Move the cursor to the opposite edge of the window when a drag reaches an edge, so
+    /// dragging never runs out of room.
+    pub wrap_cursor: bool,
+    /// Holding any of these keys while dragging the center handle drops the selection onto the
+    /// scene surface under the pointer, with its bounds resting on the surface.
+    pub surface_snap_keys: Vec<KeyCode>,
+    /// Turn the local Y axis of the dropped entities to the normal of the surface.
+    pub align_to_surface: bool,
+    /// Holding any of these keys while dragging a translation handle snaps onto the closest
+    /// vertex, edge midpoint or face center of another mesh near the pointer.
+    pub vertex_snap_keys: Vec<KeyCode>,
+    /// Distance in pixels from the pointer within which a point is snapped to.
+    pub vertex_snap_radius: f32,
+    pub snap_source: SnapSource,
+    /// Pressing this key turns the local Y axis of the selection to the surface under the
+    /// mouse, see [`AlignToSurface`](align::AlignToSurface).
+    pub align_key: Option<KeyCode>,
+    /// Clicking an axis handle while holding this key starts an array along or around that axis,
+    /// see [`ArrayTool`](array::ArrayTool).
+    pub array_key: Option<KeyCode>,
+    /// Drags move the origin of the selected entities but leave their mesh and children where
+    /// they are in world space, for fixing a badly placed pivot. The mesh is copied first when
+    /// it is shared. These drags are not recorded in the
+    /// [`TransformHistory`](history::TransformHistory), which can't put the geometry back, and
+    /// the changes it has of the edited entities and their children are forgotten.
+    pub affect_origin_only: bool,
+}
+
+impl Default for GizmoSettings {
+    fn default() -> Self {
+        Self {
+            render_mode: GizmoRenderMode::default(),
+            full_rotation_rings: false,
+            precision_keys: vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            precision_factor: 0.1,
+            drag_threshold: 4.0,
+            wrap_cursor: false,
+            surface_snap_keys: vec![KeyCode::AltLeft, KeyCode::AltRight],
+            align_to_surface: false,
+            vertex_snap_keys: vec![KeyCode::KeyV],
+            vertex_snap_radius: 15.0,
+            snap_source: SnapSource::default(),
+            align_key: Some(KeyCode::KeyN),
+            array_key: Some(KeyCode::KeyR),
+            affect_origin_only: false,
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug, Default)]
 pub struct PickSelection {
     pub is_selected: bool,
     pub initial_transform: Transform,
@@ -19,10 +163,26 @@ pub struct PickSelection {
 /// Marks the current active gizmo interaction
 #[derive(Clone, Copy, Debug, PartialEq, Component)]
 pub enum TransformGizmoInteraction {
-    TranslateAxis { original: Vec3, axis: Vec3 },
-    TranslatePlane { original: Vec3, normal: Vec3 },
-    RotateAxis { original: Vec3, axis: Vec3 },
-    ScaleAxis { original: Vec3, axis: Vec3 },
+    TranslateAxis {
+        original: Vec3,
+        axis: Vec3,
+    },
+    TranslatePlane {
+        original: Vec3,
+        normal: Vec3,
+    },
+    RotateAxis {
+        original: Vec3,
+        axis: Vec3,
+    },
+    ScaleAxis {
+        original: Vec3,
+        axis: Vec3,
+    },
+    /// Rotate around the view direction with the outer ring.
+    RotateView,
+    /// Free rotation by dragging inside the rotation arcs.
+    Trackball,
 }
 
 #[derive(Default, PartialEq, Component)]
@@ -32,10 +192,51 @@ pub struct TransformGizmo {
     // much total dragging has occurred without accumulating error across frames.
     drag_start: Option<Vec3>,
     screen_drag_start: Vec2,
+    // Pointer position the drag math works from, the sum of the pointer deltas since the drag
+    // started scaled down while the precision keys are held, so changing the gain mid-drag
+    // continues from where the handle is instead of jumping.
+    drag_pointer: Vec2,
+    // Angle a RotateAxis or RotateView drag has turned on screen, summed up move by move so
+    // wrapping the cursor keeps turning past half a turn
+    drag_angle: ScreenAngle,
+    // Whether the pointer left the dead zone of the current drag
+    drag_committed: bool,
+    // Jump of the last cursor wrap, taken out of the next pointer delta
+    pending_warp: Option<Vec2>,
     // Initial transform of the gizmo
     initial_transform: Transform,
     initial_global_transform: GlobalTransform,
     alignment_rotation: Quat,
+    // Handle under the pointer and handle being dragged, drawn highlighted
+    hovered: Option<Entity>,
+    active: Option<Entity>,
+    // Rotation so far of a RotateAxis drag, drawn as a pie slice
+    rotation_drag: Option<feedback::RotationDrag>,
+    // Point a translation drag snaps to, drawn as a marker
+    snap_target: Option<Vec3>,
+    // Offset from the pivot to the point that is moved onto the snap target
+    snap_offset: Option<Vec3>,
+    // Whether the selection follows the mouse without a button held, see `GrabSelection`
+    grabbing: bool,
+    // The grab was put down or cancelled with a mouse button that is still held
+    grab_released: bool,
+}
+
+impl TransformGizmo {
+    /// Whether the selection follows the mouse, see [`duplicate::GrabSelection`]. Stays set
+    /// until the mouse button that ended the grab is released, so its click can be told apart
+    /// from one that selects.
+    pub fn is_grabbing(&self) -> bool {
+        self.grabbing
+    }
+}
+
+/// A handle was pressed and released without moving past
+/// [`GizmoSettings::drag_threshold`], for example to pick that axis for keyboard entry.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GizmoHandleClicked {
+    pub handle: Entity,
+    pub interaction: TransformGizmoInteraction,
 }
 
 pub fn ray_from_screenspace(
@@ -53,25 +254,76 @@ pub fn ray_from_screenspace(
         .ok()
 }
 
-fn intersect_plane(ray: Ray3d, plane_normal: Vec3, plane_origin: Vec3) -> Option<Vec3> {
-    // assuming vectors are all normalized
-    let denominator = ray.direction.dot(plane_normal);
-    if denominator.abs() > f32::EPSILON {
-        let point_to_point = plane_origin - ray.origin;
-        let intersect_dist = plane_normal.dot(point_to_point) / denominator;
-        let intersect_position = ray.direction * intersect_dist + ray.origin;
-        Some(intersect_position)
-    } else {
-        None
-    }
-}
-
 pub struct TransformGizmoPlugin;
 
 impl Plugin for TransformGizmoPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, crate::mesh::spawn_gizmo)
-            .add_systems(Update, check_selection)
+        app.init_resource::<GizmoSettings>()
+            .init_resource::<history::TransformHistory>()
+            .init_resource::<array::ArrayTool>()
+            .init_resource::<vertex_snap::SnapPointCache>()
+            .register_type::<GizmoSettings>()
+            .register_type::<TransformConstraints>()
+            .register_type::<array::ArrayTool>()
+            .register_type::<mirror::Mirrored>()
+            .register_type::<mirror::MirroredBy>()
+            .add_systems(
+                Startup,
+                (
+                    crate::mesh::spawn_gizmo,
+                    feedback::spawn_rotation_feedback,
+                    feedback::spawn_snap_marker,
+                    array::spawn_array_label,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    check_selection,
+                    apply_render_mode.run_if(resource_changed::<GizmoSettings>),
+                    apply_line_width.run_if(on_message::<WindowScaleFactorChanged>),
+                    highlight_handles,
+                    facing::flip_handles.after(check_selection),
+                    facing::fade_handles.after(facing::flip_handles),
+                    facing::face_view_handles.after(check_selection),
+                    feedback::update_rotation_feedback,
+                    feedback::update_snap_marker,
+                    vertex_snap::invalidate_snap_points,
+                    align::align_key,
+                    history::history_keys,
+                    duplicate::duplicate_key,
+                    duplicate::update_grab,
+                    array::array_keys,
+                    array::update_array.after(array::array_keys),
+                    array::update_array_label.after(array::array_keys),
+                    constraints::hide_locked_handles,
+                ),
+            )
+            .add_observer(align::align_to_surface)
+            .add_observer(layout::align_selection)
+            .add_observer(layout::distribute_selection)
+            .add_observer(duplicate::duplicate_selection)
+            .add_observer(duplicate::start_grab)
+            .add_observer(array::start_array)
+            .add_observer(array::confirm_array)
+            .add_observer(array::cancel_array)
+            .add_observer(mirror::mirror_selection)
+            .add_observer(bake::bake_transform)
+            .add_observer(history::undo)
+            .add_observer(history::redo)
+            .add_systems(
+                PostUpdate,
+                (
+                    mirror::follow_mirrors,
+                    (
+                        origin::start_origin_edit,
+                        origin::keep_geometry_in_place,
+                        origin::end_origin_edit,
+                    )
+                        .chain(),
+                )
+                    .before(TransformSystems::Propagate),
+            )
             //.add_plugins(DebugVectorsPlugin)
             .add_plugins(MaterialPlugin::<GizmoMaterial>::default());
     }
@@ -79,12 +331,12 @@ impl Plugin for TransformGizmoPlugin {
 
 fn check_selection(
     mut query: Query<(Entity, &PickSelection, &GlobalTransform), Without<TransformGizmo>>,
-    mut gizmo: Query<(&mut Transform, &mut TransformGizmo)>,
+    mut gizmo: Query<(&mut Transform, &mut TransformGizmo, &mut Visibility)>,
 ) {
     //let selected: Vec<_> = query.iter().filter(|(_, p, _)| p.is_selected).collect();
     //info!("selected.len() {}", selected.len());
 
-    let Ok((mut gizmo_transform, mut gizmo)) = gizmo.single_mut() else {
+    let Ok((mut gizmo_transform, mut gizmo, mut visibility)) = gizmo.single_mut() else {
         warn!("getting main gizmo error");
         return;
     };
@@ -99,6 +351,12 @@ fn check_selection(
         transform.rotation = trans.rotation();
         pick_count += 1;
     }
+    // without a selection there is no center to put the gizmo at
+    if pick_count == 0 {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Visible);
     transform.translation /= pick_count as f32;
 
     gizmo_transform.translation = transform.translation;
@@ -144,11 +402,9 @@ pub fn drag_start(
     mut gizmo: Query<(&GlobalTransform, &Transform, &mut TransformGizmo)>,
     mut hit_reader: MessageReader<PointerHits>,
     mut item_query: Query<(&Transform, &mut PickSelection), Without<TransformGizmo>>,
+    settings: Res<GizmoSettings>,
 ) {
-    debug_assert_eq!(interaction_query.iter().len(), 13);
-
     let mut min_depth = f32::MAX;
-    let mut min_entity = None;
     let mut min_data = None;
     for hit_message in hit_reader.read() {
         //debug_print_hits(msg_i, hit_message, &transform_query, &drag);
@@ -167,7 +423,6 @@ pub fn drag_start(
         {
             if data.depth != 0.0 && data.depth < min_depth {
                 min_depth = data.depth;
-                min_entity = Some(drag.entity);
                 min_data = Some(data);
             }
         }
@@ -175,8 +430,6 @@ pub fn drag_start(
 
     //let position = drag.pointer_location.position;
     //info!("click position: {:#?}", position);
-    //println!("min entity: {:?}", min_entity);
-    debug_assert_eq!(min_entity, Some(drag.entity));
     //println!("min data:   {:?}", min_data);
 
     // if there are multiple gizmos allowed we're going to have to find the one clicked
@@ -187,11 +440,8 @@ pub fn drag_start(
         return;
     };
 
-    for (selected_transform, mut pick) in item_query
-        .iter_mut()
-        .filter(|(_, pick)| pick.is_selected)
+    for (selected_transform, mut pick) in item_query.iter_mut().filter(|(_, pick)| pick.is_selected)
     {
-        println!("saving initial_transform");
         pick.initial_transform = *selected_transform;
     }
 
@@ -199,24 +449,100 @@ pub fn drag_start(
         warn!("transform_query couldn't find entity from click");
         return;
     };
+    let Some(drag_start) = min_data.and_then(|data| data.position) else {
+        warn!("no hit position for the dragged handle");
+        return;
+    };
 
     transform_gizmo.current_interaction = Some(*interaction);
-    transform_gizmo.drag_start = Some(min_data.unwrap().position.unwrap());
+    transform_gizmo.drag_start = Some(drag_start);
     transform_gizmo.screen_drag_start = drag.pointer_location.position;
+    transform_gizmo.drag_pointer = drag.pointer_location.position;
+    transform_gizmo.drag_angle = ScreenAngle::new(drag.pointer_location.position);
+    transform_gizmo.drag_committed = settings.drag_threshold <= 0.0;
     transform_gizmo.initial_transform = *main_transform;
     transform_gizmo.initial_global_transform = *main_global_transform;
+    transform_gizmo.active = Some(drag.entity);
+}
+
+type SelectionQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static PickSelection,
+        Option<&'static TransformConstraints>,
+        Option<&'static ChildOf>,
+    ),
+    Without<TransformGizmo>,
+>;
+
+/// Set every selected entity to the transform `update` makes from its transform at the start of
+/// the drag, held back by its [`TransformConstraints`].
+///
+/// When any of them is constrained, returns where the gizmo should go to stay on the selection:
+/// the mean world translation and the last world rotation, the same as `check_selection`.
+/// `spaces` are the global transforms of their parents.
+fn update_selection(
+    items: &mut SelectionQuery,
+    spaces: &Query<&GlobalTransform>,
+    update: impl Fn(&Transform) -> Transform,
+) -> Option<Transform> {
+    let mut constrained = false;
+    let mut follow = Transform::default();
+    let mut count = 0;
+    let selected = items.iter_mut().filter(|(_, _, pick, ..)| pick.is_selected);
+    for (_, mut transform, pick, constraints, parent) in selected {
+        let initial = &pick.initial_transform;
+        let new_transform = match constraints {
+            Some(constraints) => {
+                constrained = true;
+                constraints.apply(initial, update(initial))
+            }
+            None => update(initial),
+        };
+        write_finite(&mut transform, new_transform);
+        let world = match parent.and_then(|parent| spaces.get(parent.parent()).ok()) {
+            Some(space) => space.mul_transform(*transform),
+            None => GlobalTransform::from(*transform),
+        };
+        follow.translation += world.translation();
+        follow.rotation = world.rotation();
+        count += 1;
+    }
+    follow.translation /= count as f32;
+    constrained.then_some(follow)
+}
+
+/// Distance in pixels from the window edge at which the cursor is wrapped.
+const CURSOR_WRAP_MARGIN: f32 = 2.0;
+
+/// Camera, window, settings and input read while dragging a handle.
+#[derive(SystemParam)]
+pub struct DragInput<'w, 's> {
+    pick_cam: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<GizmoPickSource>>,
+    windows: Query<'w, 's, &'static mut Window, With<PrimaryWindow>>,
+    settings: Res<'w, GizmoSettings>,
+    grid: Option<Res<'w, GridSettings>>,
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    surface: SurfaceRayCast<'w, 's>,
+    snap: VertexSnap<'w, 's>,
+    bounds: EntityBounds<'w, 's>,
+    /// Global transforms of the parents of the selection, see [`update_selection`].
+    spaces: Query<'w, 's, &'static GlobalTransform>,
 }
 
 pub fn drag_axis(
     drag: On<Pointer<Drag>>,
-    pick_cam: Query<(&Camera, &GlobalTransform), With<GizmoPickSource>>,
-    windows: Query<&mut Window, With<PrimaryWindow>>,
+    input: DragInput,
     mut gizmo_query: Query<(&mut Transform, &GlobalTransform, &mut TransformGizmo)>,
     debug_vectors: Option<ResMut<DebugVectors>>,
     //mut rotate_debug_vectors: Option<ResMut<RotateDebugVectors>>,
-    mut item_query: Query<(&mut Transform, &PickSelection), Without<TransformGizmo>>,
+    mut item_query: SelectionQuery,
 ) {
-    let Ok((mut gizmo_local_transform, _gizmo_global_transform, gizmo)) = gizmo_query.single_mut()
+    let Ok((mut gizmo_local_transform, _gizmo_global_transform, mut gizmo)) =
+        gizmo_query.single_mut()
     else {
         let len = gizmo_query.iter().len();
         warn!("error gizmo_query.single_mut() len: {}", len);
@@ -226,7 +552,19 @@ pub fn drag_axis(
     //let initial_transform = gizmo.initial_global_transform;
     //let rotation_offset = gizmo.alignment_rotation;
 
-    let Ok(window) = windows.single() else {
+    let DragInput {
+        pick_cam,
+        mut windows,
+        settings,
+        grid,
+        keyboard,
+        mut surface,
+        mut snap,
+        bounds,
+        spaces,
+    } = input;
+
+    let Ok(mut window) = windows.single_mut() else {
         warn!("no window");
         return;
     };
@@ -236,9 +574,44 @@ pub fn drag_axis(
         return;
     };
 
-    let current_pointer = drag.pointer_location.position;
+    let gain = if keyboard.any_pressed(settings.precision_keys.iter().copied()) {
+        settings.precision_factor
+    } else {
+        1.0
+    };
+    let mut delta = drag.delta;
+    // the first move after a wrap includes the jump to the other edge
+    if let Some(warp) = gizmo.pending_warp
+        && delta.distance(warp) < delta.length()
+    {
+        delta -= warp;
+        gizmo.pending_warp = None;
+    }
+    gizmo.drag_pointer += delta * gain;
+    let current_pointer = gizmo.drag_pointer;
+    // what is under the cursor is picked where the cursor is, not where the scaled pointer is
+    let cursor = drag.pointer_location.position;
+
+    if settings.wrap_cursor
+        && let Some(wrapped) = wrap_position(
+            drag.pointer_location.position,
+            window.size(),
+            CURSOR_WRAP_MARGIN,
+        )
+    {
+        window.set_cursor_position(Some(wrapped));
+        gizmo.pending_warp = Some(wrapped - drag.pointer_location.position);
+    }
+
+    // small accidental movements while clicking a handle don't move anything
+    if !gizmo.drag_committed {
+        if drag.distance.length() < settings.drag_threshold {
+            return;
+        }
+        gizmo.drag_committed = true;
+    }
     let Some(picking_ray) =
-        ray_from_screenspace(current_pointer, picking_camera, global_cam_tran, window)
+        ray_from_screenspace(current_pointer, picking_camera, global_cam_tran, &window)
     else {
         warn!("error creating ray");
         return;
@@ -254,73 +627,210 @@ pub fn drag_axis(
         return;
     };
 
+    let snapping = matches!(
+        interaction,
+        TransformGizmoInteraction::TranslateAxis { .. }
+            | TransformGizmoInteraction::TranslatePlane { .. }
+    ) && keyboard.any_pressed(settings.vertex_snap_keys.iter().copied());
+    if !snapping {
+        gizmo.snap_offset = None;
+    } else if gizmo.snap_offset.is_none() {
+        let vertex = match settings.snap_source {
+            SnapSource::Pivot => None,
+            SnapSource::NearestVertex => {
+                snap.selection_vertex(picking_camera, global_cam_tran, cursor)
+            }
+        };
+        gizmo.snap_offset = Some(vertex.map_or(Vec3::ZERO, |vertex| {
+            vertex - gizmo_local_transform.translation
+        }));
+    }
+    let snap_target = match snapping {
+        true => snap.target(
+            picking_camera,
+            global_cam_tran,
+            cursor,
+            settings.vertex_snap_radius,
+        ),
+        false => None,
+    };
+    gizmo.snap_target = snap_target;
+    let snap_source = initial_transform.translation + gizmo.snap_offset.unwrap_or_default();
+
     match interaction {
         TransformGizmoInteraction::TranslateAxis { original: _, axis } => {
-            let normalized_translation_axis = (initial_transform.rotation * axis).normalize();
-            let vertical_vector = picking_ray
-                .direction
-                .cross(normalized_translation_axis)
-                .normalize();
-            let plane_normal = normalized_translation_axis
-                .cross(vertical_vector)
-                .normalize();
-            let plane_origin = drag_start;
-            let Some(ray_plane_intersection) =
-                intersect_plane(picking_ray, plane_normal, plane_origin)
+            let Some(normalized_translation_axis) =
+                (initial_transform.rotation * axis).try_normalize()
             else {
-                warn!("what? None cursor_plane_intersection");
+                warn!("degenerate translation axis {:?}", axis);
                 return;
             };
-            let cursor_vector: Vec3 = ray_plane_intersection - plane_origin;
-            let plane = InfinitePlane3d::new(normalized_translation_axis);
-            let isometry = Isometry3d::from_translation(plane_origin);
-            let signed_distance = plane.signed_distance(isometry, ray_plane_intersection);
-            let translation = normalized_translation_axis * signed_distance;
-            let new_translation = initial_transform.translation + translation;
-
-            if let Some(mut debug) = debug_vectors {
-                *debug = DebugVectors {
-                    translation_axis: normalized_translation_axis,
-                    vertical_vector,
-                    plane_normal,
-                    picking_ray,
-                    plane_origin,
-                    ray_plane_intersection,
-                    cursor_vector,
+            let drag_plane = axis_drag_plane(*picking_ray.direction, normalized_translation_axis);
+            let plane_distance = drag_plane.and_then(|(vertical_vector, plane_normal)| {
+                let plane_origin = drag_start;
+                let ray_plane_intersection =
+                    intersect_plane(picking_ray, plane_normal, plane_origin)?;
+                let plane = InfinitePlane3d::new(normalized_translation_axis);
+                let isometry = Isometry3d::from_translation(plane_origin);
+                let signed_distance = plane.signed_distance(isometry, ray_plane_intersection);
+                Some((
                     signed_distance,
-                };
+                    DebugVectors {
+                        translation_axis: normalized_translation_axis,
+                        vertical_vector,
+                        plane_normal,
+                        picking_ray,
+                        plane_origin,
+                        ray_plane_intersection,
+                        cursor_vector: ray_plane_intersection - plane_origin,
+                        signed_distance,
+                    },
+                ))
+            });
+            let mut signed_distance = match plane_distance {
+                Some((signed_distance, vectors)) => {
+                    if let Some(mut debug) = debug_vectors {
+                        *debug = vectors;
+                    }
+                    signed_distance
+                }
+                // the ray runs along the axis or grazes the drag plane, measure on screen instead
+                None => {
+                    let (Ok(origin), Ok(axis_end)) = (
+                        picking_camera.world_to_viewport(global_cam_tran, drag_start),
+                        picking_camera.world_to_viewport(
+                            global_cam_tran,
+                            drag_start + normalized_translation_axis,
+                        ),
+                    ) else {
+                        warn!("translation axis is not on screen");
+                        return;
+                    };
+                    let Some(signed_distance) = screen_axis_distance(
+                        origin,
+                        axis_end,
+                        gizmo.screen_drag_start,
+                        current_pointer,
+                    ) else {
+                        // pointing straight at the camera, nothing sensible to do this frame
+                        return;
+                    };
+                    signed_distance
+                }
+            };
+            if let Some(grid) = &grid {
+                signed_distance = grid.snap_distance(
+                    initial_transform.translation,
+                    normalized_translation_axis,
+                    signed_distance,
+                );
+            }
+            let mut translation = normalized_translation_axis * signed_distance;
+            if let Some(target) = snap_target {
+                translation = normalized_translation_axis
+                    * (target - snap_source).dot(normalized_translation_axis);
+            }
+            let new_translation = initial_transform.translation + translation;
+            if !new_translation.is_finite() {
+                warn!("non-finite translation {:?}", new_translation);
+                return;
             }
 
             gizmo_local_transform.translation = new_translation;
 
-            for (mut selected_transform, pick) in
-                item_query.iter_mut().filter(|(_, pick)| pick.is_selected)
-            {
-                println!("selected_transform");
-                selected_transform.translation = pick.initial_transform.translation + translation;
+            if let Some(follow) = update_selection(&mut item_query, &spaces, |initial| Transform {
+                translation: initial.translation + translation,
+                ..*initial
+            }) {
+                gizmo_local_transform.translation = follow.translation;
             }
         }
         TransformGizmoInteraction::TranslatePlane { original, normal } => {
+            // the center handle drops the selection onto the surface under the pointer
+            if original == Vec3::ZERO
+                && keyboard.any_pressed(settings.surface_snap_keys.iter().copied())
+                && let Some(cursor_ray) =
+                    ray_from_screenspace(cursor, picking_camera, global_cam_tran, &window)
+                && let Some(hit) = surface.cast(cursor_ray)
+            {
+                // rest the selection on the surface instead of sinking it to its origin, its
+                // bounds are from the last frame and relative to where the gizmo is now
+                let selected = item_query
+                    .iter()
+                    .filter(|(_, _, pick, ..)| pick.is_selected)
+                    .map(|(entity, ..)| entity);
+                let lift =
+                    bounds.extent_below(selected, gizmo_local_transform.translation, hit.normal);
+                let point = hit.point + hit.normal * lift;
+                let translation = point - initial_transform.translation;
+                let align = |rotation: Quat| match settings.align_to_surface {
+                    true => align_axis(rotation, Vec3::Y, hit.normal).unwrap_or(rotation),
+                    false => rotation,
+                };
+                gizmo_local_transform.translation = point;
+                gizmo_local_transform.rotation = align(initial_transform.rotation);
+                if let Some(follow) =
+                    update_selection(&mut item_query, &spaces, |initial| Transform {
+                        translation: initial.translation + translation,
+                        rotation: align(initial.rotation),
+                        ..*initial
+                    })
+                {
+                    gizmo_local_transform.translation = follow.translation;
+                    gizmo_local_transform.rotation = follow.rotation;
+                }
+                return;
+            }
+
             // if this is the center of the gizmo screen space translator
             let plane_normal = if original == Vec3::ZERO {
                 global_cam_tran.forward().as_vec3()
             } else {
                 initial_transform.rotation * normal
             };
-            let Some(ray_plane_intersection) =
-                intersect_plane(picking_ray, plane_normal, drag_start)
-            else {
-                warn!("what? None cursor_plane_intersection");
+            let translation = match intersect_plane(picking_ray, plane_normal, drag_start) {
+                Some(ray_plane_intersection) => Some(ray_plane_intersection - drag_start),
+                // grazing the handle plane, move in the view plane and flatten onto the handle
+                // plane
+                None => {
+                    intersect_plane(picking_ray, global_cam_tran.forward().as_vec3(), drag_start)
+                        .and_then(|view_hit| {
+                            project_onto_plane(view_hit - drag_start, plane_normal)
+                        })
+                }
+            };
+            let Some(mut translation) = translation else {
+                warn!("no intersection with the drag plane");
                 return;
             };
-            let translation = ray_plane_intersection - drag_start;
-            gizmo_local_transform.translation = gizmo.initial_transform.translation + translation;
+            if let Some(grid) = &grid {
+                // a plane handle can only put the pivot on the grid lines in its own plane
+                let normal = (original != Vec3::ZERO).then_some(plane_normal);
+                translation =
+                    grid.snap_translation(initial_transform.translation, translation, normal);
+            }
+            if let Some(target) = snap_target {
+                // the center handle moves freely, the plane handles stay on their plane
+                translation = match original == Vec3::ZERO {
+                    true => target - snap_source,
+                    false => project_onto_plane(target - snap_source, plane_normal)
+                        .unwrap_or(translation),
+                };
+            }
+            let new_translation = gizmo.initial_transform.translation + translation;
+            if !new_translation.is_finite() {
+                warn!("non-finite translation {:?}", new_translation);
+                return;
+            }
+            gizmo_local_transform.translation = new_translation;
+            // undo the alignment of a surface drop when its keys are released
+            gizmo_local_transform.rotation = initial_transform.rotation;
 
-            for (mut selected_transform, pick) in
-                item_query.iter_mut().filter(|(_, pick)| pick.is_selected)
-            {
-                println!("selected_transform");
-                selected_transform.translation = pick.initial_transform.translation + translation;
+            if let Some(follow) = update_selection(&mut item_query, &spaces, |initial| Transform {
+                translation: initial.translation + translation,
+                ..*initial
+            }) {
+                gizmo_local_transform.translation = follow.translation;
             }
         }
         TransformGizmoInteraction::RotateAxis { original: _, axis } => {
@@ -331,12 +841,13 @@ pub fn drag_axis(
                 return;
             };
 
-            let mut start = gizmo.screen_drag_start - screen_gizmo_center;
-            start.y = -start.y;
-            let mut current = current_pointer - screen_gizmo_center;
-            current.y = -current.y;
-
-            let mut diff_angle = start.angle_to(current);
+            let Some(mut diff_angle) = gizmo
+                .drag_angle
+                .update(screen_gizmo_center, current_pointer)
+            else {
+                // too close to the center of the gizmo to have a direction
+                return;
+            };
 
             // Your current logic for calculating rotation through screen-space angles is a solid
             // start, but it contains a common pitfall: flipping the rotation direction depending
@@ -358,16 +869,78 @@ pub fn drag_axis(
                 diff_angle *= -1.0;
             }
 
+            // the pie slice starts where the arc was grabbed
+            let start = project_onto_plane(drag_start - initial_transform.translation, world_axis)
+                .and_then(Vec3::try_normalize);
+            gizmo.rotation_drag =
+                start
+                    .zip(world_axis.try_normalize())
+                    .map(|(start, axis)| feedback::RotationDrag {
+                        center: initial_transform.translation,
+                        axis,
+                        start,
+                        angle: diff_angle,
+                    });
+
             let rotation = Quat::from_axis_angle(axis, diff_angle);
-            gizmo_local_transform.rotation = initial_transform.rotation * rotation;
+            let new_rotation = initial_transform.rotation * rotation;
+            if !new_rotation.is_finite() {
+                warn!("non-finite rotation {:?}", new_rotation);
+                return;
+            }
+            gizmo_local_transform.rotation = new_rotation;
 
-            for (mut selected_transform, pick) in
-                item_query.iter_mut().filter(|(_, pick)| pick.is_selected)
-            {
-                selected_transform.rotation = pick.initial_transform.rotation * rotation; 
+            if let Some(follow) = update_selection(&mut item_query, &spaces, |initial| Transform {
+                rotation: initial.rotation * rotation,
+                ..*initial
+            }) {
+                gizmo_local_transform.rotation = follow.rotation;
             }
         }
-        TransformGizmoInteraction::ScaleAxis { original: _, axis } => {
+        TransformGizmoInteraction::RotateView | TransformGizmoInteraction::Trackball => {
+            let rotation = if interaction == TransformGizmoInteraction::RotateView {
+                let Ok(screen_gizmo_center) = picking_camera
+                    .world_to_viewport(global_cam_tran, initial_transform.translation)
+                else {
+                    warn!("what no screen_pos!");
+                    return;
+                };
+                let Some(diff_angle) = gizmo
+                    .drag_angle
+                    .update(screen_gizmo_center, current_pointer)
+                else {
+                    return;
+                };
+                // counter-clockwise on screen is counter-clockwise around the axis pointing at
+                // the camera
+                Quat::from_axis_angle(global_cam_tran.back().as_vec3(), diff_angle)
+            } else {
+                let Some(rotation) = trackball_rotation(
+                    current_pointer - gizmo.screen_drag_start,
+                    global_cam_tran.right().as_vec3(),
+                    global_cam_tran.up().as_vec3(),
+                ) else {
+                    return;
+                };
+                rotation
+            };
+
+            // the axis is in world space so the rotation is applied on the outside
+            let new_rotation = rotation * initial_transform.rotation;
+            if !new_rotation.is_finite() {
+                warn!("non-finite rotation {:?}", new_rotation);
+                return;
+            }
+            gizmo_local_transform.rotation = new_rotation;
+
+            if let Some(follow) = update_selection(&mut item_query, &spaces, |initial| Transform {
+                rotation: rotation * initial.rotation,
+                ..*initial
+            }) {
+                gizmo_local_transform.rotation = follow.rotation;
+            }
+        }
+        TransformGizmoInteraction::ScaleAxis { .. } => {
             /*
             let normalized_translation_axis = (initial_transform.rotation * axis).normalize();
             let vertical_vector = picking_ray
@@ -393,24 +966,154 @@ pub fn drag_axis(
 
 pub fn drag_end(
     _drag: On<Pointer<DragEnd>>,
-    mut commands: Commands,
-    transform_query: Query<
-        (
-            Entity,
-            &TransformGizmoInteraction,
-            Option<&ChildOf>,
-            &mut Transform,
-        ),
-        Without<TransformGizmo>,
-    >,
     mut gizmo: Query<&mut TransformGizmo>,
+    items: Query<(Entity, &Transform, &PickSelection), Without<TransformGizmoInteraction>>,
+    mut history: ResMut<history::TransformHistory>,
+    settings: Res<GizmoSettings>,
 ) {
     let Ok(mut gizmo) = gizmo.single_mut() else {
         warn!("getting main gizmo error");
         return;
     };
 
+    if gizmo.drag_committed && !settings.affect_origin_only {
+        let mut edit = history::TransformEdit::new("drag");
+        for (entity, transform, pick) in items.iter().filter(|(_, _, pick)| pick.is_selected) {
+            edit.push(entity, pick.initial_transform, *transform);
+        }
+        history.record(edit);
+    }
+
     gizmo.current_interaction = None;
     gizmo.drag_start = None;
+    gizmo.active = None;
+    gizmo.rotation_drag = None;
+    gizmo.drag_committed = false;
+    gizmo.pending_warp = None;
+    gizmo.snap_target = None;
+    gizmo.snap_offset = None;
     info!("drag_end");
 }
+
+/// Turn a press and release on a handle that never left the drag dead zone into a
+/// [`GizmoHandleClicked`]. Click comes before DragEnd so the drag state is still there.
+pub fn handle_click(
+    click: On<Pointer<Click>>,
+    mut commands: Commands,
+    gizmo: Query<&TransformGizmo>,
+    interaction_query: Query<&TransformGizmoInteraction>,
+) {
+    let Ok(gizmo) = gizmo.single() else {
+        warn!("getting main gizmo error");
+        return;
+    };
+    if gizmo.drag_committed {
+        return;
+    }
+    let Ok(interaction) = interaction_query.get(click.entity) else {
+        return;
+    };
+    commands.trigger(GizmoHandleClicked {
+        handle: click.entity,
+        interaction: *interaction,
+    });
+}
+
+pub fn hover_start(over: On<Pointer<Over>>, mut gizmo: Query<&mut TransformGizmo>) {
+    let Ok(mut gizmo) = gizmo.single_mut() else {
+        warn!("getting main gizmo error");
+        return;
+    };
+    gizmo.hovered = Some(over.entity);
+}
+
+pub fn hover_end(out: On<Pointer<Out>>, mut gizmo: Query<&mut TransformGizmo>) {
+    let Ok(mut gizmo) = gizmo.single_mut() else {
+        warn!("getting main gizmo error");
+        return;
+    };
+    if gizmo.hovered == Some(out.entity) {
+        gizmo.hovered = None;
+    }
+}
+
+/// Move the gizmo meshes to the render layers of the current [`GizmoRenderMode`] and switch
+/// their materials in or out of x-ray.
+fn apply_render_mode(
+    settings: Res<GizmoSettings>,
+    mut targets: Query<
+        (&mut RenderLayers, &MeshMaterial3d<GizmoMaterial>),
+        With<GizmoRenderModeTarget>,
+    >,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+) {
+    let (layers, x_ray) = settings.render_mode.layers_and_x_ray();
+    for (mut target_layers, material) in targets.iter_mut() {
+        target_layers.set_if_neq(layers.clone());
+        // only touch the asset when it changes so it isn't uploaded again
+        if materials.get(material).is_some_and(|m| m.x_ray != x_ray)
+            && let Some(material) = materials.get_mut(material)
+        {
+            material.x_ray = x_ray;
+        }
+    }
+}
+
+/// Give the gizmo line materials their [`GizmoLineWidth`] in the pixels of the primary window
+/// again after it moved to a screen with another scale factor.
+fn apply_line_width(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    lines: Query<(&GizmoLineWidth, &MeshMaterial3d<GizmoMaterial>)>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+) {
+    let scale_factor = windows.single().map_or(1.0, Window::scale_factor);
+    for (width, material) in lines.iter() {
+        let line_width = width.physical(scale_factor);
+        if materials
+            .get(material)
+            .is_some_and(|m| m.line_width != line_width)
+            && let Some(material) = materials.get_mut(material)
+        {
+            material.line_width = line_width;
+        }
+    }
+}
+
+/// Brighten the dragged handle, or the hovered one when nothing is dragged, and the line drawn
+/// over it.
+fn highlight_handles(
+    gizmo: Query<&TransformGizmo, Changed<TransformGizmo>>,
+    handles: Query<(Entity, &MeshMaterial3d<GizmoMaterial>), With<TransformGizmoInteraction>>,
+    overlays: Query<(&ChildOf, &MeshMaterial3d<GizmoMaterial>), Without<TransformGizmoInteraction>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+) {
+    let Ok(gizmo) = gizmo.single() else {
+        return;
+    };
+
+    let highlight = |entity: Entity| {
+        if gizmo.active == Some(entity) {
+            0.5
+        } else if gizmo.active.is_none() && gizmo.hovered == Some(entity) {
+            0.3
+        } else {
+            0.0
+        }
+    };
+    let handles = handles.iter();
+    let overlays = overlays
+        .iter()
+        .filter(|(child_of, _)| handles.clone().any(|(e, _)| e == child_of.parent()))
+        .map(|(child_of, material)| (child_of.parent(), material));
+    for (entity, material) in handles.clone().chain(overlays) {
+        let highlight = highlight(entity);
+        // only touch the asset when it changes so it isn't uploaded again every frame
+        if materials
+            .get(material)
+            .is_some_and(|m| m.highlight != highlight)
+            && let Some(material) = materials.get_mut(material)
+        {
+            material.highlight = highlight;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::{math::Affine3A, prelude::*};
+
+use crate::{
+    gizmo::{GizmoSettings, PickSelection, TransformGizmo, history::TransformHistory},
+    mesh::bake::transform_mesh,
+};
+
+/// Entity whose origin is being moved by a drag in
+/// [`affect_origin_only`](GizmoSettings::affect_origin_only) mode, with what it looked like
+/// when the drag started.
+#[derive(Component)]
+pub(crate) struct OriginEdit {
+    initial: Transform,
+    children: Vec<(Entity, Transform)>,
+    /// Copy of the mesh the entity draws, made unique to it, and the vertices it started with.
+    mesh: Option<(Handle<Mesh>, Mesh)>,
+}
+
+/// Start an origin edit on the selected entities when a drag starts.
+pub(crate) fn start_origin_edit(
+    mut commands: Commands,
+    settings: Res<GizmoSettings>,
+    gizmo: Query<&TransformGizmo>,
+    items: Query<(Entity, &PickSelection, Option<&Mesh3d>), Without<OriginEdit>>,
+    children: Query<&Children>,
+    transforms: Query<&Transform>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !settings.affect_origin_only
+        || !gizmo
+            .iter()
+            .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        return;
+    }
+    for (entity, pick, mesh) in items.iter().filter(|(_, pick, _)| pick.is_selected) {
+        let children = children
+            .get(entity)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| Some((*child, *transforms.get(*child).ok()?)))
+            .collect();
+        // the mesh may be shared, the other users keep the original
+        let mesh = mesh
+            .and_then(|mesh| meshes.get(mesh))
+            .cloned()
+            .map(|original| {
+                let copy = meshes.add(original.clone());
+                commands.entity(entity).insert(Mesh3d(copy.clone()));
+                (copy, original)
+            });
+        commands.entity(entity).insert(OriginEdit {
+            initial: pick.initial_transform,
+            children,
+            mesh,
+        });
+    }
+}
+
+/// Move the children and the mesh of entities being edited back to where they started in world
+/// space, undoing the move of their origin.
+pub(crate) fn keep_geometry_in_place(
+    edits: Query<(Ref<Transform>, &OriginEdit)>,
+    mut children: Query<&mut Transform, Without<OriginEdit>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (transform, edit) in edits.iter().filter(|(transform, _)| transform.is_changed()) {
+        let delta: Affine3A = transform.compute_affine().inverse() * edit.initial.compute_affine();
+        if !delta.is_finite() {
+            continue;
+        }
+        for (child, initial) in &edit.children {
+            if let Ok(mut child) = children.get_mut(*child) {
+                *child = Transform::from_matrix(Mat4::from(delta * initial.compute_affine()));
+            }
+        }
+        if let Some((handle, original)) = &edit.mesh
+            && let Some(mesh) = meshes.get_mut(handle)
+        {
+            *mesh = original.clone();
+            transform_mesh(mesh, delta);
+        }
+    }
+}
+
+/// End the origin edits once the drag is over. The recorded changes of the edited entities and
+/// their children are forgotten, undoing them would put back a transform without the mesh and
+/// children that moved against it.
+pub(crate) fn end_origin_edit(
+    mut commands: Commands,
+    settings: Res<GizmoSettings>,
+    gizmo: Query<&TransformGizmo>,
+    edits: Query<(Entity, &Transform, &OriginEdit)>,
+    mut history: ResMut<TransformHistory>,
+) {
+    if settings.affect_origin_only
+        && gizmo
+            .iter()
+            .any(|gizmo| gizmo.current_interaction.is_some())
+    {
+        return;
+    }
+    for (entity, transform, edit) in edits.iter() {
+        if *transform != edit.initial {
+            history.forget(entity);
+            for (child, _) in &edit.children {
+                history.forget(*child);
+            }
+        }
+        commands.entity(entity).remove::<OriginEdit>();
+    }
+}
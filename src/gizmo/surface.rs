@@ -0,0 +1,74 @@
+use bevy::{camera::visibility::RenderLayers, ecs::system::SystemParam, prelude::*};
+
+use crate::gizmo::{PickSelection, TransformGizmo};
+
+/// Point of the scene surface hit by a ray.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SurfaceHit {
+    pub entity: Entity,
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+/// Tells the scene geometry the selection can be moved onto apart from the gizmo and the
+/// selection itself.
+#[derive(SystemParam)]
+pub(crate) struct SceneFilter<'w, 's> {
+    parents: Query<'w, 's, &'static ChildOf>,
+    selection: Query<'w, 's, &'static PickSelection>,
+    gizmos: Query<'w, 's, (), With<TransformGizmo>>,
+    scene: Query<'w, 's, (Option<&'static RenderLayers>, Option<&'static Pickable>)>,
+}
+
+impl SceneFilter<'_, '_> {
+    fn selected(&self, entity: Entity) -> bool {
+        self.selection
+            .get(entity)
+            .is_ok_and(|pick| pick.is_selected)
+    }
+
+    /// Whether `entity` or one of its ancestors is selected.
+    pub fn in_selection(&self, entity: Entity) -> bool {
+        self.selected(entity)
+            || self
+                .parents
+                .iter_ancestors(entity)
+                .any(|e| self.selected(e))
+    }
+
+    /// Whether `entity` is pickable and on the default render layer, and neither it nor one of
+    /// its ancestors is the gizmo or selected.
+    pub fn is_scene(&self, entity: Entity) -> bool {
+        let Ok((layers, pickable)) = self.scene.get(entity) else {
+            return false;
+        };
+        let excluded = |entity: Entity| self.gizmos.contains(entity) || self.selected(entity);
+        layers
+            .unwrap_or(&RenderLayers::default())
+            .intersects(&RenderLayers::default())
+            && pickable.is_none_or(|pickable| pickable.is_hoverable)
+            && !excluded(entity)
+            && !self.parents.iter_ancestors(entity).any(excluded)
+    }
+}
+
+/// Casts rays against the scene geometry the selection can be dropped onto.
+#[derive(SystemParam)]
+pub(crate) struct SurfaceRayCast<'w, 's> {
+    ray_cast: MeshRayCast<'w, 's>,
+    scene: SceneFilter<'w, 's>,
+}
+
+impl SurfaceRayCast<'_, '_> {
+    /// Closest scene surface along `ray`.
+    pub fn cast(&mut self, ray: Ray3d) -> Option<SurfaceHit> {
+        let filter = |entity: Entity| self.scene.is_scene(entity);
+        let settings = MeshRayCastSettings::default().with_filter(&filter);
+        let (entity, hit) = self.ray_cast.cast_ray(ray, &settings).first()?;
+        Some(SurfaceHit {
+            entity: *entity,
+            point: hit.point,
+            normal: hit.normal.try_normalize()?,
+        })
+    }
+}
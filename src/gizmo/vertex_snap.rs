@@ -0,0 +1,235 @@
+use bevy::{
+    camera::primitives::Aabb, ecs::system::SystemParam, mesh::PrimitiveTopology,
+    platform::collections::HashMap, prelude::*,
+};
+
+use crate::gizmo::surface::SceneFilter;
+
+/// Vertices of a mesh in its local space.
+fn vertices(mesh: &Mesh) -> Vec<Vec3> {
+    mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(|positions| positions.as_float3())
+        .map(|positions| positions.iter().copied().map(Vec3::from).collect())
+        .unwrap_or_default()
+}
+
+/// Points of a mesh worth snapping to in its local space: the vertices and, for triangle lists,
+/// the middle of every edge and the center of every triangle.
+fn snap_points(mesh: &Mesh) -> Vec<Vec3> {
+    let mut points = vertices(mesh);
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return points;
+    }
+    let vertex_count = points.len();
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..vertex_count).collect(),
+    };
+    for triangle in indices.chunks_exact(3) {
+        if triangle.iter().any(|&index| index >= vertex_count) {
+            continue;
+        }
+        let [a, b, c] = [
+            points[triangle[0]],
+            points[triangle[1]],
+            points[triangle[2]],
+        ];
+        points.extend([
+            (a + b) * 0.5,
+            (b + c) * 0.5,
+            (c + a) * 0.5,
+            (a + b + c) / 3.0,
+        ]);
+    }
+    points
+}
+
+/// [`snap_points`] of a mesh, its vertices first.
+struct SnapPoints {
+    points: Vec<Vec3>,
+    vertex_count: usize,
+}
+
+impl SnapPoints {
+    fn new(mesh: &Mesh) -> Self {
+        Self {
+            points: snap_points(mesh),
+            vertex_count: mesh.count_vertices(),
+        }
+    }
+
+    fn vertices(&self) -> &[Vec3] {
+        &self.points[..self.vertex_count.min(self.points.len())]
+    }
+}
+
+/// Snap points of the meshes snapped to so far, so a drag doesn't rebuild them on every move.
+#[derive(Resource, Default)]
+pub(crate) struct SnapPointCache(HashMap<AssetId<Mesh>, SnapPoints>);
+
+/// Drop the cached snap points of meshes that changed or are gone.
+pub(crate) fn invalidate_snap_points(
+    mut events: MessageReader<AssetEvent<Mesh>>,
+    mut cache: ResMut<SnapPointCache>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            cache.0.remove(id);
+        }
+    }
+}
+
+/// Whether the screen rectangle around `aabb` comes within `radius` pixels of `pointer`. A box
+/// that is partly behind the camera is always near.
+fn near_on_screen(
+    aabb: &Aabb,
+    transform: &GlobalTransform,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    pointer: Vec2,
+    radius: f32,
+) -> bool {
+    if !radius.is_finite() {
+        return true;
+    }
+    let (local_min, local_max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+    let (mut min, mut max) = (Vec2::INFINITY, Vec2::NEG_INFINITY);
+    for corner in 0..8 {
+        let pick = BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0);
+        let point = transform.transform_point(Vec3::select(pick, local_max, local_min));
+        let Ok(screen) = camera.world_to_viewport(camera_transform, point) else {
+            return true;
+        };
+        min = min.min(screen);
+        max = max.max(screen);
+    }
+    pointer.cmpge(min - radius).all() && pointer.cmple(max + radius).all()
+}
+
+/// Which points [`VertexSnap`] looks at.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SnapTo {
+    /// Vertices, edge midpoints and face centers of the scene.
+    Scene,
+    /// Vertices of the selection.
+    SelectionVertices,
+}
+
+/// Finds the mesh points closest to the pointer on screen.
+#[derive(SystemParam)]
+pub(crate) struct VertexSnap<'w, 's> {
+    meshes: Res<'w, Assets<Mesh>>,
+    cache: ResMut<'w, SnapPointCache>,
+    mesh_entities: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Mesh3d,
+            &'static GlobalTransform,
+            &'static ViewVisibility,
+            Option<&'static Aabb>,
+        ),
+    >,
+    scene: SceneFilter<'w, 's>,
+}
+
+impl VertexSnap<'_, '_> {
+    /// Vertex, edge midpoint or face center of the scene closest to `pointer`, if it is within
+    /// `radius` pixels.
+    pub fn target(
+        &mut self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        pointer: Vec2,
+        radius: f32,
+    ) -> Option<Vec3> {
+        self.closest(camera, camera_transform, pointer, radius, SnapTo::Scene)
+    }
+
+    /// Vertex of the selection closest to `pointer`.
+    pub fn selection_vertex(
+        &mut self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        pointer: Vec2,
+    ) -> Option<Vec3> {
+        let to = SnapTo::SelectionVertices;
+        self.closest(camera, camera_transform, pointer, f32::INFINITY, to)
+    }
+
+    fn closest(
+        &mut self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        pointer: Vec2,
+        radius: f32,
+        to: SnapTo,
+    ) -> Option<Vec3> {
+        let mut closest = None;
+        let mut closest_distance = radius;
+        for (entity, mesh, transform, visibility, aabb) in &self.mesh_entities {
+            let wanted = match to {
+                SnapTo::Scene => self.scene.is_scene(entity),
+                SnapTo::SelectionVertices => self.scene.in_selection(entity),
+            };
+            if !visibility.get() || !wanted {
+                continue;
+            }
+            if let Some(aabb) = aabb
+                && !near_on_screen(aabb, transform, camera, camera_transform, pointer, radius)
+            {
+                continue;
+            }
+            let id = mesh.id();
+            if !self.cache.0.contains_key(&id) {
+                let Some(mesh) = self.meshes.get(id) else {
+                    continue;
+                };
+                self.cache.0.insert(id, SnapPoints::new(mesh));
+            }
+            let points = &self.cache.0[&id];
+            let points = match to {
+                SnapTo::Scene => &points.points[..],
+                SnapTo::SelectionVertices => points.vertices(),
+            };
+            for point in points {
+                let point = transform.transform_point(*point);
+                let Ok(screen) = camera.world_to_viewport(camera_transform, point) else {
+                    continue;
+                };
+                let distance = screen.distance(pointer);
+                if distance < closest_distance {
+                    closest_distance = distance;
+                    closest = Some(point);
+                }
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{asset::RenderAssetUsages, mesh::Indices};
+
+    #[test]
+    fn snap_points_include_edge_midpoints_and_face_centers() {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [3.0, 0.0, 0.0], [0.0, 3.0, 0.0]],
+        );
+        mesh.insert_indices(Indices::U32(vec![0, 1, 2]));
+        let points = snap_points(&mesh);
+        assert_eq!(points.len(), 7);
+        assert!(points.contains(&Vec3::new(1.5, 0.0, 0.0)));
+        assert!(points.contains(&Vec3::new(1.5, 1.5, 0.0)));
+        assert!(points.contains(&Vec3::new(1.0, 1.0, 0.0)));
+        assert_eq!(SnapPoints::new(&mesh).vertices().len(), 3);
+    }
+}
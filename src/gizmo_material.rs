@@ -4,27 +4,89 @@ use bevy::{
     prelude::*,
     reflect::TypePath,
     render::render_resource::{
-        AsBindGroup, RenderPipelineDescriptor, SpecializedMeshPipelineError,
+        AsBindGroup, CompareFunction, RenderPipelineDescriptor, SpecializedMeshPipelineError,
     },
     shader::ShaderRef,
 };
 
+use crate::mesh::line::ATTRIBUTE_LINE_OTHER;
+
 // https://docs.rs/bevy/latest/bevy/pbr/trait.Material.html
 
+/// Unlit color for the gizmo and axis meshes, shaded by the angle to the camera so the shapes
+/// read as 3D.
+///
+/// Meshes built from a [`LineStrip`](crate::mesh::line::LineStrip) are drawn as anti-aliased
+/// lines `line_width` physical pixels wide whatever their distance to the camera.
 #[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[bind_group_data(GizmoMaterialKey)]
 pub struct GizmoMaterial {
     #[uniform(0)]
     pub color: LinearRgba,
+    /// Width in physical pixels of line meshes, unused by other meshes.
+    #[uniform(0)]
+    pub line_width: f32,
+    /// How much of the color is mixed towards white, for hovered and dragged handles.
+    #[uniform(0)]
+    pub highlight: f32,
+    /// How much faces turned away from the camera are darkened, 0 is flat.
+    #[uniform(0)]
+    pub shading: f32,
+    /// Local axis of the handle, it fades out as it gets aligned with the view direction.
+    /// `Vec3::ZERO` never fades.
+    #[uniform(0)]
+    pub fade_axis: Vec3,
+    /// Draw on top of the scene from the main camera, dimming and dashing the parts that are
+    /// behind scene geometry. The camera needs a `DepthPrepass` to tell them apart.
+    pub x_ray: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GizmoMaterialKey {
+    x_ray: bool,
+}
+
+impl From<&GizmoMaterial> for GizmoMaterialKey {
+    fn from(material: &GizmoMaterial) -> Self {
+        GizmoMaterialKey {
+            x_ray: material.x_ray,
+        }
+    }
 }
 
 impl From<Color> for GizmoMaterial {
     fn from(color: Color) -> Self {
         GizmoMaterial {
             color: color.into(),
+            line_width: 0.0,
+            highlight: 0.0,
+            shading: 0.5,
+            fade_axis: Vec3::ZERO,
+            x_ray: false,
         }
     }
 }
 
+impl GizmoMaterial {
+    pub fn line(color: Color, line_width: f32) -> Self {
+        GizmoMaterial {
+            line_width,
+            shading: 0.0,
+            ..GizmoMaterial::from(color)
+        }
+    }
+
+    pub fn with_fade_axis(mut self, fade_axis: Vec3) -> Self {
+        self.fade_axis = fade_axis;
+        self
+    }
+
+    pub fn with_x_ray(mut self, x_ray: bool) -> Self {
+        self.x_ray = x_ray;
+        self
+    }
+}
+
 impl Material for GizmoMaterial {
     fn vertex_shader() -> ShaderRef {
         "shaders/gizmo_material.wgsl".into()
@@ -35,16 +97,49 @@ impl Material for GizmoMaterial {
     }
 
     fn alpha_mode(&self) -> AlphaMode {
-        AlphaMode::Opaque
+        // the anti-aliased edges of lines, the dimmed hidden parts and the faded handles need
+        // blending
+        if self.color.alpha < 1.0
+            || self.line_width > 0.0
+            || self.x_ray
+            || self.fade_axis != Vec3::ZERO
+        {
+            AlphaMode::Blend
+        } else {
+            AlphaMode::Opaque
+        }
     }
 
     fn specialize(
         _pipeline: &MaterialPipeline,
         descriptor: &mut RenderPipelineDescriptor,
-        _layout: &MeshVertexBufferLayoutRef,
-        _key: MaterialPipelineKey<Self>,
+        layout: &MeshVertexBufferLayoutRef,
+        key: MaterialPipelineKey<Self>,
     ) -> Result<(), SpecializedMeshPipelineError> {
         descriptor.primitive.cull_mode = None;
+        if key.bind_group_data.x_ray {
+            // the shader does its own depth test against the prepass
+            if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+                depth_stencil.depth_compare = CompareFunction::Always;
+                depth_stencil.depth_write_enabled = false;
+            }
+            descriptor.vertex.shader_defs.push("GIZMO_XRAY".into());
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("GIZMO_XRAY".into());
+            }
+        }
+        if layout.0.contains(ATTRIBUTE_LINE_OTHER) {
+            let vertex_layout = layout.0.get_layout(&[
+                Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+                Mesh::ATTRIBUTE_UV_0.at_shader_location(1),
+                ATTRIBUTE_LINE_OTHER.at_shader_location(2),
+            ])?;
+            descriptor.vertex.buffers = vec![vertex_layout];
+            descriptor.vertex.shader_defs.push("GIZMO_LINE".into());
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("GIZMO_LINE".into());
+            }
+        }
         Ok(())
     }
 }
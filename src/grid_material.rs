@@ -0,0 +1,61 @@
+use bevy::{
+    mesh::MeshVertexBufferLayoutRef,
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::TypePath,
+    render::render_resource::{
+        AsBindGroup, RenderPipelineDescriptor, SpecializedMeshPipelineError,
+    },
+    shader::ShaderRef,
+};
+
+/// Infinite grid drawn in the fragment shader, see `shaders/grid_material.wgsl`.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct GridMaterial {
+    #[uniform(0)]
+    pub minor_color: LinearRgba,
+    #[uniform(0)]
+    pub major_color: LinearRgba,
+    #[uniform(0)]
+    pub x_axis_color: LinearRgba,
+    #[uniform(0)]
+    pub y_axis_color: LinearRgba,
+    #[uniform(0)]
+    pub z_axis_color: LinearRgba,
+    /// Size of the smallest grid cell, zooming out multiplies it by `major_every`.
+    #[uniform(0)]
+    pub spacing: f32,
+    /// Number of minor cells in a major cell.
+    #[uniform(0)]
+    pub major_every: f32,
+    /// Distance from the camera at which the grid has faded out when close to the plane.
+    #[uniform(0)]
+    pub fade_distance: f32,
+    /// 0 for the XZ plane, 1 for XY and 2 for YZ.
+    #[uniform(0)]
+    pub plane: u32,
+}
+
+impl Material for GridMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/grid_material.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/grid_material.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
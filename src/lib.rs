@@ -1,6 +1,6 @@
-pub mod mesh;
-mod gizmo_material;
-pub mod gizmo;
 pub mod axis;
-
-
+pub mod gizmo;
+mod gizmo_material;
+mod grid_material;
+pub mod mesh;
+pub mod view_cube;
@@ -3,9 +3,10 @@ use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_panorbit_camera::{PanOrbitCamera, PanOrbitCameraPlugin};
 use scratch_transform::{
-    axis::AxisPlugin,
-    gizmo::{GizmoPickSource, PickSelection, TransformGizmoPlugin},
+    axis::{AxisPlugin, AxisSettings},
+    gizmo::{GizmoPickSource, PickSelection, TransformGizmo, TransformGizmoPlugin},
     mesh::GIZMO_RENDER_LAYER,
+    view_cube::ViewCubePlugin,
 };
 
 fn main() {
@@ -18,8 +19,13 @@ fn main() {
             WorldInspectorPlugin::default(),
             PanOrbitCameraPlugin,
             AxisPlugin,
+            ViewCubePlugin,
             WireframePlugin::default(),
         ))
+        .insert_resource(AxisSettings {
+            tick_spacing: Some(1.0),
+            ..default()
+        })
         .add_systems(Startup, setup)
         .add_systems(Update, cam_copy)
         .run();
@@ -116,8 +122,16 @@ fn cam_copy(
     *gizmo_transform = *pan_orbit_transform;
 }
 
-fn cube_click(mut click: On<Pointer<Click>>, mut cubes: Query<(Entity, &mut PickSelection)>) {
+fn cube_click(
+    mut click: On<Pointer<Click>>,
+    mut cubes: Query<(Entity, &mut PickSelection)>,
+    gizmo: Query<&TransformGizmo>,
+) {
     click.propagate(false);
+    // The click that put down a grab keeps the grabbed selection
+    if gizmo.iter().any(TransformGizmo::is_grabbing) {
+        return;
+    }
     for (_, mut cube) in cubes.iter_mut() {
         cube.is_selected = false;
     }
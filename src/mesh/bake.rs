@@ -0,0 +1,111 @@
+use bevy::{
+    math::Affine3A,
+    mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+    prelude::*,
+};
+
+/// Move the vertices of `mesh` by `transform`, turning the normals and tangents with it.
+///
+/// A transform that mirrors the mesh also reverses the winding of its triangles so their front
+/// faces stay on the outside.
+pub fn transform_mesh(mesh: &mut Mesh, transform: Affine3A) {
+    let linear = Mat3::from(transform.matrix3);
+    let normal_matrix = linear.inverse().transpose();
+    let mirrored = linear.determinant() < 0.0;
+
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        for position in positions.iter_mut() {
+            *position = transform.transform_point3(Vec3::from(*position)).into();
+        }
+    }
+    if let Some(VertexAttributeValues::Float32x3(normals)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_NORMAL)
+    {
+        for normal in normals.iter_mut() {
+            *normal = (normal_matrix * Vec3::from(*normal))
+                .normalize_or_zero()
+                .into();
+        }
+    }
+    if let Some(VertexAttributeValues::Float32x4(tangents)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_TANGENT)
+    {
+        for tangent in tangents.iter_mut() {
+            let [x, y, z, w] = *tangent;
+            let direction = (linear * Vec3::new(x, y, z)).normalize_or_zero();
+            let handedness = if mirrored { -w } else { w };
+            *tangent = direction.extend(handedness).into();
+        }
+    }
+
+    if mirrored && mesh.primitive_topology() == PrimitiveTopology::TriangleList {
+        match mesh.indices_mut() {
+            Some(Indices::U16(indices)) => flip_winding(indices),
+            Some(Indices::U32(indices)) => flip_winding(indices),
+            None => {}
+        }
+    }
+}
+
+fn flip_winding<T>(indices: &mut [T]) {
+    for triangle in indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::RenderAssetUsages;
+
+    fn triangle() -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        );
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 3]);
+        mesh.insert_indices(Indices::U32(vec![0, 1, 2]));
+        mesh
+    }
+
+    fn positions(mesh: &Mesh) -> &[[f32; 3]] {
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|positions| positions.as_float3())
+            .unwrap()
+    }
+
+    #[test]
+    fn scale_moves_positions_and_keeps_normals_unit() {
+        let mut mesh = triangle();
+        let transform = Affine3A::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 4.0),
+            Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            Vec3::Y,
+        );
+        transform_mesh(&mut mesh, transform);
+        let moved = Vec3::from(positions(&mesh)[1]);
+        assert!(moved.abs_diff_eq(Vec3::new(2.0, 1.0, 0.0), 1e-5));
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("no normals");
+        };
+        assert!(Vec3::from(normals[0]).abs_diff_eq(Vec3::NEG_Y, 1e-5));
+    }
+
+    #[test]
+    fn mirroring_flips_the_winding() {
+        let mut mesh = triangle();
+        transform_mesh(&mut mesh, Affine3A::from_scale(Vec3::new(-1.0, 1.0, 1.0)));
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("no indices");
+        };
+        assert_eq!(indices, &vec![0, 2, 1]);
+    }
+}
@@ -0,0 +1,103 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    mesh::{Indices, MeshVertexAttribute, VertexFormat},
+    prelude::*,
+    render::render_resource::PrimitiveTopology,
+};
+
+/// Position of the other end of the segment a line vertex belongs to.
+pub const ATTRIBUTE_LINE_OTHER: MeshVertexAttribute =
+    MeshVertexAttribute::new("LineOther", 988_540_917, VertexFormat::Float32x3);
+
+/// A polyline drawn by [`GizmoMaterial`](crate::gizmo_material::GizmoMaterial) at a constant
+/// width in pixels.
+///
+/// Every segment becomes a quad whose four vertices sit on the segment end points, the vertex
+/// shader pushes them apart in screen space. `UV_0.x` is the side of the line (-1 or 1) and
+/// `UV_0.y` is 0 at the start of the segment and 1 at its end.
+#[derive(Debug, Clone)]
+pub struct LineStrip {
+    pub points: Vec<Vec3>,
+    /// Connect the last point back to the first.
+    pub closed: bool,
+}
+
+impl LineStrip {
+    pub fn segment(start: Vec3, end: Vec3) -> Self {
+        LineStrip {
+            points: vec![start, end],
+            closed: false,
+        }
+    }
+
+    /// An arc of `angle` radians in the XZ plane starting on the X axis, the same shape as a
+    /// [`TruncatedTorus`](crate::mesh::truncated_torus::TruncatedTorus) of that radius.
+    pub fn arc(radius: f32, angle: f32, segments: usize) -> Self {
+        let segments = segments.max(1);
+        let points = (0..=segments)
+            .map(|segment| {
+                let theta = angle * segment as f32 / segments as f32;
+                Vec3::new(theta.cos() * radius, 0.0, theta.sin() * radius)
+            })
+            .collect();
+        LineStrip {
+            points,
+            closed: false,
+        }
+    }
+}
+
+/// Disconnected line segments drawn like a [`LineStrip`].
+#[derive(Debug, Clone, Default)]
+pub struct LineList {
+    pub segments: Vec<(Vec3, Vec3)>,
+}
+
+impl From<LineStrip> for Mesh {
+    fn from(line: LineStrip) -> Self {
+        let mut segments: Vec<(Vec3, Vec3)> = line
+            .points
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+        if line.closed && line.points.len() > 2 {
+            segments.push((line.points[line.points.len() - 1], line.points[0]));
+        }
+        Mesh::from(LineList { segments })
+    }
+}
+
+impl From<LineList> for Mesh {
+    fn from(line: LineList) -> Self {
+        let segments = line.segments;
+        let n_vertices = segments.len() * 4;
+        let mut positions: Vec<[f32; 3]> = Vec::with_capacity(n_vertices);
+        let mut others: Vec<[f32; 3]> = Vec::with_capacity(n_vertices);
+        let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(n_vertices);
+        let mut indices: Vec<u32> = Vec::with_capacity(segments.len() * 6);
+
+        for (i, (start, end)) in segments.into_iter().enumerate() {
+            for (position, other, end_of_segment) in [(start, end, 0.0), (end, start, 1.0)] {
+                for side in [-1.0, 1.0] {
+                    positions.push(position.into());
+                    others.push(other.into());
+                    uvs.push([side, end_of_segment]);
+                }
+            }
+
+            let first = (i * 4) as u32;
+            indices.extend_from_slice(&[first, first + 1, first + 2]);
+            indices.extend_from_slice(&[first + 1, first + 3, first + 2]);
+        }
+
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        );
+        mesh.insert_indices(Indices::U32(indices));
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(ATTRIBUTE_LINE_OTHER, others);
+        mesh
+    }
+}
@@ -1,12 +1,20 @@
 use crate::{
-    gizmo::{TransformGizmo, TransformGizmoInteraction, drag_start, drag_axis, drag_end},
+    gizmo::{
+        GizmoLineWidth, GizmoRenderModeTarget, GizmoSettings, TransformGizmo,
+        TransformGizmoInteraction, drag_axis, drag_end, drag_start, handle_click, hover_end,
+        hover_start,
+    },
     gizmo_material::GizmoMaterial,
 };
-use bevy::{camera::visibility::{Layer, RenderLayers}, light::NotShadowCaster, prelude::*};
+use bevy::{camera::visibility::Layer, light::NotShadowCaster, prelude::*, window::PrimaryWindow};
 
+pub mod bake;
+pub mod line;
 pub mod truncated_torus;
 
 const GIZMO_AXIS_LENGTH: f32 = 1.3;
+const GIZMO_LINE_WIDTH: GizmoLineWidth = GizmoLineWidth(2.0);
+pub(crate) const GIZMO_ARC_RADIUS: f32 = 1.0;
 pub const GIZMO_RENDER_LAYER: Layer = 1;
 
 /// Startup system that builds the procedural mesh and materials of the gizmo.
@@ -14,9 +22,15 @@ pub fn spawn_gizmo(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<GizmoMaterial>>,
+    settings: Res<GizmoSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
+    let (layers, x_ray) = settings.render_mode.layers_and_x_ray();
+    let layers = (layers, GizmoRenderModeTarget);
+    let scale_factor = windows.single().map_or(1.0, Window::scale_factor);
+
     // Define gizmo size
-    let arc_radius = 1.;
+    let arc_radius = GIZMO_ARC_RADIUS;
     let plane_size = GIZMO_AXIS_LENGTH * 0.25;
     let plane_offset = plane_size / 2. + GIZMO_AXIS_LENGTH * 0.2;
 
@@ -31,31 +45,72 @@ pub fn spawn_gizmo(
     });
     let plane_mesh = meshes.add(Plane3d::default().mesh().size(plane_size, plane_size));
     let sphere_mesh = meshes.add(Sphere { radius: 0.2 });
-    let rotation_mesh = meshes.add(Mesh::from(truncated_torus::TruncatedTorus {
-        radius: arc_radius,
-        ring_radius: 0.04,
-        ..Default::default()
-    }));
+    let arc_angle = if settings.full_rotation_rings {
+        std::f32::consts::TAU
+    } else {
+        std::f32::consts::PI / 2.0
+    };
+    let arc_segments = (arc_angle / std::f32::consts::TAU * 128.0) as usize;
+    let rotation_mesh = meshes.add(
+        truncated_torus::TruncatedTorus {
+            radius: arc_radius,
+            ring_radius: 0.04,
+            angle: arc_angle,
+            ..Default::default()
+        }
+        .mesh()
+        .segments(arc_segments)
+        .sides(12),
+    );
+    // drawn over the arcs so they keep a visible width when the gizmo is far away
+    let rotation_line_mesh = meshes.add(line::LineStrip::arc(arc_radius, arc_angle, arc_segments));
+    // turned to face the camera every frame
+    let view_ring_radius = arc_radius * 1.15;
+    let view_ring_mesh = meshes.add(
+        truncated_torus::TruncatedTorus::full(view_ring_radius, 0.03)
+            .mesh()
+            .segments(128)
+            .sides(12),
+    );
+    let view_ring_line_mesh = meshes.add(line::LineStrip::arc(
+        view_ring_radius,
+        std::f32::consts::TAU,
+        128,
+    ));
+    let trackball_mesh = meshes.add(Circle::new(arc_radius));
 
     // Define gizmo materials
     let (s, l) = (0.8, 0.6);
-    let gizmo_matl_x = materials.add(GizmoMaterial::from(Color::hsl(0.0, s, l)));
-    let gizmo_matl_y = materials.add(GizmoMaterial::from(Color::hsl(120.0, s, l)));
-    let gizmo_matl_z = materials.add(GizmoMaterial::from(Color::hsl(240.0, s, l)));
-    let gizmo_matl_x_sel = materials.add(GizmoMaterial::from(Color::hsl(0.0, s, l)));
-    let gizmo_matl_y_sel = materials.add(GizmoMaterial::from(Color::hsl(120.0, s, l)));
-    let gizmo_matl_z_sel = materials.add(GizmoMaterial::from(Color::hsl(240.0, s, l)));
-    let gizmo_matl_v_sel = materials.add(GizmoMaterial::from(Color::hsl(0., 0.0, l)));
+    let (color_x, color_y, color_z) = (
+        Color::hsl(0.0, s, l),
+        Color::hsl(120.0, s, l),
+        Color::hsl(240.0, s, l),
+    );
+    let color_v = Color::hsl(0., 0.0, l);
+    // every handle gets its own material so it can be highlighted on its own
+    let gizmo_material = |color: Color| GizmoMaterial::from(color).with_x_ray(x_ray);
+    // arrows fade out when they point at the camera, their local Y is the translation axis
+    let arrow_material = |color: Color| gizmo_material(color).with_fade_axis(Vec3::Y);
+    let line_width = GIZMO_LINE_WIDTH.physical(scale_factor);
+    let line_material = |color: Color| GizmoMaterial::line(color, line_width).with_x_ray(x_ray);
+    let gizmo_line_x = materials.add(line_material(Color::hsl(0.0, s, l)));
+    let gizmo_line_y = materials.add(line_material(Color::hsl(120.0, s, l)));
+    let gizmo_line_z = materials.add(line_material(Color::hsl(240.0, s, l)));
+    let gizmo_line_v = materials.add(line_material(color_v));
 
     // Build the gizmo using the variables above.
     commands
-        .spawn((TransformGizmo::default(), Transform::default(), Visibility::Visible))
+        .spawn((
+            TransformGizmo::default(),
+            Transform::default(),
+            Visibility::Visible,
+        ))
         .with_children(|parent| {
             // Translation Axes
             parent
                 .spawn((
                     Mesh3d(arrow_tail_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_x.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_x))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_z(std::f32::consts::PI / 2.0),
                         Vec3::new(GIZMO_AXIS_LENGTH / 2.0, 0.0, 0.0),
@@ -65,15 +120,18 @@ pub fn spawn_gizmo(
                         axis: Vec3::X,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
             parent
                 .spawn((
                     Mesh3d(arrow_tail_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_y.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_y))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_y(std::f32::consts::PI / 2.0),
                         Vec3::new(0.0, GIZMO_AXIS_LENGTH / 2.0, 0.0),
@@ -83,15 +141,18 @@ pub fn spawn_gizmo(
                         axis: Vec3::Y,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
             parent
                 .spawn((
                     Mesh3d(arrow_tail_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_z.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_z))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_x(std::f32::consts::PI / 2.0),
                         Vec3::new(0.0, 0.0, GIZMO_AXIS_LENGTH / 2.0),
@@ -101,17 +162,20 @@ pub fn spawn_gizmo(
                         axis: Vec3::Z,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
 
             // Translation Handles
             parent
                 .spawn((
                     Mesh3d(cone_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_x_sel.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_x))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_z(std::f32::consts::PI / -2.0),
                         Vec3::new(GIZMO_AXIS_LENGTH, 0.0, 0.0),
@@ -121,15 +185,18 @@ pub fn spawn_gizmo(
                         axis: Vec3::X,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
             parent
                 .spawn((
                     Mesh3d(plane_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_x_sel.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_x))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_z(std::f32::consts::PI / -2.0),
                         Vec3::new(0., plane_offset, plane_offset),
@@ -140,32 +207,38 @@ pub fn spawn_gizmo(
                     },
                     //NoBackfaceCulling,
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
-                //.observe(click_plane)
-                //.observe(drag_plane);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
+            //.observe(click_plane)
+            //.observe(drag_plane);
             parent
                 .spawn((
                     Mesh3d(cone_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_y_sel.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_y))),
                     Transform::from_translation(Vec3::new(0.0, GIZMO_AXIS_LENGTH, 0.0)),
                     TransformGizmoInteraction::TranslateAxis {
                         original: Vec3::Y,
                         axis: Vec3::Y,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
             parent
                 .spawn((
                     Mesh3d(plane_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_y_sel.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_y))),
                     Transform::from_translation(Vec3::new(plane_offset, 0.0, plane_offset)),
                     TransformGizmoInteraction::TranslatePlane {
                         original: Vec3::Y,
@@ -173,17 +246,20 @@ pub fn spawn_gizmo(
                     },
                     //NoBackfaceCulling,
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
-                //.observe(click_plane)
-                //.observe(drag_plane);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
+            //.observe(click_plane)
+            //.observe(drag_plane);
             parent
                 .spawn((
                     Mesh3d(cone_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_z_sel.clone()),
+                    MeshMaterial3d(materials.add(arrow_material(color_z))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_x(std::f32::consts::PI / 2.0),
                         Vec3::new(0.0, 0.0, GIZMO_AXIS_LENGTH),
@@ -193,15 +269,18 @@ pub fn spawn_gizmo(
                         axis: Vec3::Z,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
             parent
                 .spawn((
                     Mesh3d(plane_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_z_sel.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_z))),
                     Transform::from_matrix(Mat4::from_rotation_translation(
                         Quat::from_rotation_x(std::f32::consts::PI / 2.0),
                         Vec3::new(plane_offset, plane_offset, 0.0),
@@ -212,70 +291,102 @@ pub fn spawn_gizmo(
                     },
                     //NoBackfaceCulling,
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 //.observe(click_plane)
                 //.observe(drag_plane);
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
 
             // screen space drag sphere
             parent
                 .spawn((
                     Mesh3d(sphere_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_v_sel.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_v))),
                     TransformGizmoInteraction::TranslatePlane {
                         original: Vec3::ZERO,
                         normal: Vec3::Z,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 //.observe(click_plane)
                 //.observe(drag_plane);
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
 
             // Rotation Arcs
             parent
                 .spawn((
                     Mesh3d(rotation_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_x.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_x))),
                     Transform::from_rotation(Quat::from_axis_angle(Vec3::Z, f32::to_radians(90.0))),
                     TransformGizmoInteraction::RotateAxis {
                         original: Vec3::X,
                         axis: Vec3::X,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 //.observe(click_rotate)
                 //.observe(drag_rotate);
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click)
+                .with_children(|arc| {
+                    arc.spawn((
+                        Mesh3d(rotation_line_mesh.clone()),
+                        MeshMaterial3d(gizmo_line_x.clone()),
+                        GIZMO_LINE_WIDTH,
+                        NotShadowCaster,
+                        Pickable::IGNORE,
+                        layers.clone(),
+                    ));
+                });
             parent
                 .spawn((
                     Mesh3d(rotation_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_y.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_y))),
                     TransformGizmoInteraction::RotateAxis {
                         original: Vec3::Y,
                         axis: Vec3::Y,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 //.observe(click_rotate)
                 //.observe(drag_rotate);
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click)
+                .with_children(|arc| {
+                    arc.spawn((
+                        Mesh3d(rotation_line_mesh.clone()),
+                        MeshMaterial3d(gizmo_line_y.clone()),
+                        GIZMO_LINE_WIDTH,
+                        NotShadowCaster,
+                        Pickable::IGNORE,
+                        layers.clone(),
+                    ));
+                });
             parent
                 .spawn((
                     Mesh3d(rotation_mesh.clone()),
-                    MeshMaterial3d(gizmo_matl_z.clone()),
+                    MeshMaterial3d(materials.add(gizmo_material(color_z))),
                     Transform::from_rotation(
                         Quat::from_axis_angle(Vec3::Z, f32::to_radians(90.0))
                             * Quat::from_axis_angle(Vec3::X, f32::to_radians(90.0)),
@@ -285,12 +396,68 @@ pub fn spawn_gizmo(
                         axis: Vec3::Z,
                     },
                     NotShadowCaster,
-                    RenderLayers::layer(GIZMO_RENDER_LAYER),
+                    layers.clone(),
                 ))
                 //.observe(click_rotate)
                 //.observe(drag_rotate);
                 .observe(drag_start)
                 .observe(drag_end)
-                .observe(drag_axis);
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click)
+                .with_children(|arc| {
+                    arc.spawn((
+                        Mesh3d(rotation_line_mesh.clone()),
+                        MeshMaterial3d(gizmo_line_z.clone()),
+                        GIZMO_LINE_WIDTH,
+                        NotShadowCaster,
+                        Pickable::IGNORE,
+                        layers.clone(),
+                    ));
+                });
+
+            // View Rotation Ring
+            parent
+                .spawn((
+                    Mesh3d(view_ring_mesh),
+                    MeshMaterial3d(materials.add(gizmo_material(color_v))),
+                    TransformGizmoInteraction::RotateView,
+                    NotShadowCaster,
+                    layers.clone(),
+                ))
+                .observe(drag_start)
+                .observe(drag_end)
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click)
+                .with_children(|ring| {
+                    ring.spawn((
+                        Mesh3d(view_ring_line_mesh),
+                        MeshMaterial3d(gizmo_line_v),
+                        GIZMO_LINE_WIDTH,
+                        NotShadowCaster,
+                        Pickable::IGNORE,
+                        layers.clone(),
+                    ));
+                });
+
+            // Trackball, a faint disc behind the other handles so it only catches the pointer
+            // where nothing else is
+            parent
+                .spawn((
+                    Mesh3d(trackball_mesh),
+                    MeshMaterial3d(materials.add(gizmo_material(color_v.with_alpha(0.08)))),
+                    TransformGizmoInteraction::Trackball,
+                    NotShadowCaster,
+                    layers.clone(),
+                ))
+                .observe(drag_start)
+                .observe(drag_end)
+                .observe(drag_axis)
+                .observe(hover_start)
+                .observe(hover_end)
+                .observe(handle_click);
         });
 }
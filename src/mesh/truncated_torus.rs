@@ -1,13 +1,16 @@
 use bevy::{
-    asset::RenderAssetUsages, mesh::Indices, prelude::*, render::render_resource::PrimitiveTopology
+    asset::RenderAssetUsages, mesh::Indices, prelude::*, render::render_resource::PrimitiveTopology,
 };
-/// A torus (donut) shape.
+/// A torus (donut) shape, or an arc of one when `angle` is less than a full turn.
+///
+/// The ring lies in the XZ plane around the Y axis and starts on the X axis.
 #[derive(Debug, Clone, Copy)]
 pub struct TruncatedTorus {
     pub radius: f32,
     pub ring_radius: f32,
     pub subdivisions_segments: usize,
     pub subdivisions_sides: usize,
+    /// Angle swept by the arc in radians, a full turn or more gives a closed ring.
     pub angle: f32,
 }
 
@@ -23,50 +26,105 @@ impl Default for TruncatedTorus {
     }
 }
 
-impl From<TruncatedTorus> for Mesh {
-    fn from(torus: TruncatedTorus) -> Self {
+impl TruncatedTorus {
+    /// A closed ring.
+    pub fn full(radius: f32, ring_radius: f32) -> Self {
+        TruncatedTorus {
+            radius,
+            ring_radius,
+            angle: std::f32::consts::TAU,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.angle >= std::f32::consts::TAU
+    }
+}
+
+/// Builds the [`Mesh`] of a [`TruncatedTorus`].
+#[derive(Debug, Clone, Copy)]
+pub struct TruncatedTorusMeshBuilder {
+    pub torus: TruncatedTorus,
+    /// Close the ends of an open arc with flat discs, ignored for closed rings.
+    pub end_caps: bool,
+}
+
+impl TruncatedTorusMeshBuilder {
+    pub fn segments(mut self, segments: usize) -> Self {
+        self.torus.subdivisions_segments = segments;
+        self
+    }
+
+    pub fn sides(mut self, sides: usize) -> Self {
+        self.torus.subdivisions_sides = sides;
+        self
+    }
+
+    pub fn end_caps(mut self, end_caps: bool) -> Self {
+        self.end_caps = end_caps;
+        self
+    }
+}
+
+impl Meshable for TruncatedTorus {
+    type Output = TruncatedTorusMeshBuilder;
+
+    fn mesh(&self) -> Self::Output {
+        TruncatedTorusMeshBuilder {
+            torus: *self,
+            end_caps: true,
+        }
+    }
+}
+
+impl MeshBuilder for TruncatedTorusMeshBuilder {
+    fn build(&self) -> Mesh {
         // code adapted from http://apparat-engine.blogspot.com/2013/04/procedural-meshes-torus.html
         // (source code at https://github.com/SEilers/Apparat)
+        let torus = self.torus;
+        let segments = torus.subdivisions_segments.max(1);
+        let sides = torus.subdivisions_sides.max(3);
+        let angle = torus.angle.min(std::f32::consts::TAU);
+        let caps = self.end_caps && !torus.is_closed();
 
-        let n_vertices = (torus.subdivisions_segments + 1) * (torus.subdivisions_sides + 1);
+        let n_vertices = (segments + 1) * (sides + 1) + if caps { 2 * (sides + 2) } else { 0 };
         let mut positions: Vec<[f32; 3]> = Vec::with_capacity(n_vertices);
         let mut normals: Vec<[f32; 3]> = Vec::with_capacity(n_vertices);
         let mut uvs: Vec<[f32; 2]> = Vec::with_capacity(n_vertices);
 
-        let segment_stride = torus.angle / torus.subdivisions_segments as f32;
-        let side_stride = 2.0 * std::f32::consts::PI / torus.subdivisions_sides as f32;
+        let segment_stride = angle / segments as f32;
+        let side_stride = std::f32::consts::TAU / sides as f32;
 
-        for segment in 0..=torus.subdivisions_segments {
+        // point on the tube surface and its normal, pointing away from the center of the tube
+        let surface = |theta: f32, phi: f32| {
+            let outward = Vec3::new(theta.cos(), 0.0, theta.sin());
+            let normal = outward * phi.cos() + Vec3::Y * phi.sin();
+            (outward * torus.radius + normal * torus.ring_radius, normal)
+        };
+
+        for segment in 0..=segments {
             let theta = segment_stride * segment as f32;
-            let segment_pos = Vec3::new(theta.cos(), 0.0, theta.sin() * torus.radius);
 
-            for side in 0..=torus.subdivisions_sides {
+            for side in 0..=sides {
                 let phi = side_stride * side as f32;
+                let (position, normal) = surface(theta, phi);
 
-                let x = theta.cos() * (torus.radius + torus.ring_radius * phi.cos());
-                let z = theta.sin() * (torus.radius + torus.ring_radius * phi.cos());
-                let y = torus.ring_radius * phi.sin();
-
-                let normal = segment_pos.cross(Vec3::Y).normalize();
-
-                positions.push([x, y, z]);
+                positions.push(position.into());
                 normals.push(normal.into());
-                uvs.push([
-                    segment as f32 / torus.subdivisions_segments as f32,
-                    side as f32 / torus.subdivisions_sides as f32,
-                ]);
+                uvs.push([segment as f32 / segments as f32, side as f32 / sides as f32]);
             }
         }
 
-        let n_faces = (torus.subdivisions_segments) * (torus.subdivisions_sides);
-        let n_triangles = n_faces * 2;
+        let n_faces = segments * sides;
+        let n_triangles = n_faces * 2 + if caps { 2 * sides } else { 0 };
         let n_indices = n_triangles * 3;
 
         let mut indices: Vec<u32> = Vec::with_capacity(n_indices);
 
-        let n_vertices_per_row = torus.subdivisions_sides + 1;
-        for segment in 0..torus.subdivisions_segments {
-            for side in 0..torus.subdivisions_sides {
+        let n_vertices_per_row = sides + 1;
+        for segment in 0..segments {
+            for side in 0..sides {
                 let lt = side + segment * n_vertices_per_row;
                 let rt = (side + 1) + segment * n_vertices_per_row;
 
@@ -83,6 +141,33 @@ impl From<TruncatedTorus> for Mesh {
             }
         }
 
+        if caps {
+            // flat discs facing along the arc, backwards at the start and forwards at the end
+            for (theta, sign) in [(0.0, -1.0), (angle, 1.0)] {
+                let normal = Vec3::new(-theta.sin(), 0.0, theta.cos()) * sign;
+                let center = positions.len() as u32;
+                positions.push((Vec3::new(theta.cos(), 0.0, theta.sin()) * torus.radius).into());
+                normals.push(normal.into());
+                uvs.push([0.5, 0.5]);
+                for side in 0..=sides {
+                    let phi = side_stride * side as f32;
+                    let (position, _) = surface(theta, phi);
+                    positions.push(position.into());
+                    normals.push(normal.into());
+                    uvs.push([0.5 + 0.5 * phi.cos(), 0.5 + 0.5 * phi.sin()]);
+                }
+                for side in 0..sides as u32 {
+                    let (a, b) = (center + 1 + side, center + 2 + side);
+                    // keep the winding counter-clockwise seen from outside
+                    if sign > 0.0 {
+                        indices.extend_from_slice(&[center, a, b]);
+                    } else {
+                        indices.extend_from_slice(&[center, b, a]);
+                    }
+                }
+            }
+        }
+
         let mut mesh = Mesh::new(
             PrimitiveTopology::TriangleList,
             RenderAssetUsages::default(),
@@ -94,3 +179,67 @@ impl From<TruncatedTorus> for Mesh {
         mesh
     }
 }
+
+impl From<TruncatedTorus> for Mesh {
+    fn from(torus: TruncatedTorus) -> Self {
+        torus.mesh().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(mesh: &Mesh, id: bevy::mesh::MeshVertexAttribute) -> Vec<Vec3> {
+        let values = mesh.attribute(id).and_then(|values| values.as_float3());
+        values.unwrap().iter().copied().map(Vec3::from).collect()
+    }
+
+    #[test]
+    fn normals_point_away_from_the_tube() {
+        let torus = TruncatedTorus {
+            angle: 1.3,
+            ..default()
+        };
+        let mesh = torus.mesh().segments(8).sides(6).build();
+        let positions = attribute(&mesh, Mesh::ATTRIBUTE_POSITION);
+        let normals = attribute(&mesh, Mesh::ATTRIBUTE_NORMAL);
+        let ring_vertices = 9 * 7;
+        assert_eq!(positions.len(), ring_vertices + 2 * (6 + 2));
+
+        for (position, normal) in positions.iter().zip(&normals).take(ring_vertices) {
+            assert!((normal.length() - 1.0).abs() < 1e-5);
+            let center_line = Vec3::new(position.x, 0.0, position.z).normalize() * torus.radius;
+            let away = (*position - center_line).normalize();
+            assert!(normal.abs_diff_eq(away, 1e-5), "{normal} at {position}");
+        }
+
+        // caps face away from the middle of the arc and lie flat in their plane
+        let middle = Vec3::new(0.65f32.cos(), 0.0, 0.65f32.sin()) * torus.radius;
+        for cap in positions[ring_vertices..]
+            .chunks(8)
+            .zip(normals[ring_vertices..].chunks(8))
+        {
+            let (cap_positions, cap_normals) = cap;
+            let center = cap_positions[0];
+            for (position, normal) in cap_positions.iter().zip(cap_normals) {
+                assert!((normal.length() - 1.0).abs() < 1e-5);
+                assert!(normal.dot(*position - center).abs() < 1e-5);
+                assert!(normal.dot(*position - middle) > 0.0);
+            }
+        }
+
+        // the front of every triangle faces the same way as its normals
+        let Some(Indices::U32(indices)) = mesh.indices() else {
+            panic!("no indices");
+        };
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let face = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+            assert!(
+                face.dot(normals[a] + normals[b] + normals[c]) > 0.0,
+                "{triangle:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::{
+    camera::{
+        ClearColorConfig, Viewport,
+        visibility::{Layer, RenderLayers},
+    },
+    light::NotShadowCaster,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_panorbit_camera::PanOrbitCamera;
+
+use crate::gizmo_material::GizmoMaterial;
+
+pub const VIEW_CUBE_RENDER_LAYER: Layer = 2;
+
+/// Size in logical pixels of the square corner viewport the cube is drawn in.
+const VIEW_CUBE_VIEWPORT_SIZE: f32 = 140.0;
+/// Gap in logical pixels between the viewport and the window edges.
+const VIEW_CUBE_VIEWPORT_MARGIN: f32 = 10.0;
+const VIEW_CUBE_CAMERA_DISTANCE: f32 = 4.0;
+const VIEW_CUBE_HALF_SIZE: f32 = 0.5;
+/// Thickness of the edge and corner pieces cut off the cube.
+const VIEW_CUBE_BEVEL: f32 = 0.15;
+/// Radians of orbit per pixel dragged on the cube.
+const VIEW_CUBE_ORBIT_SENSITIVITY: f32 = 0.01;
+
+/// Corner widget showing the world axes that snaps or orbits the [`PanOrbitCamera`].
+///
+/// Clicking a face, edge or corner tweens the camera to look at its focus from that direction,
+/// dragging anywhere on the cube orbits it.
+pub struct ViewCubePlugin;
+
+impl Plugin for ViewCubePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewCubeDrag>()
+            .add_systems(Startup, spawn_view_cube)
+            .add_systems(Update, (place_view_cube_viewport, sync_view_cube_camera));
+    }
+}
+
+/// Camera rendering [`VIEW_CUBE_RENDER_LAYER`] into the corner viewport.
+#[derive(Component)]
+pub struct ViewCubeCamera;
+
+/// One clickable piece of the cube: a face, an edge or a corner.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ViewCubeHandle {
+    /// Direction from the camera focus to the camera when this piece is clicked.
+    pub direction: Vec3,
+}
+
+/// Set while the cube is being dragged so the click ending the drag doesn't snap the view.
+#[derive(Resource, Default)]
+struct ViewCubeDrag(bool);
+
+/// Yaw and pitch of a [`PanOrbitCamera`] looking at its focus from `direction`.
+pub fn view_cube_yaw_pitch(direction: Vec3) -> (f32, f32) {
+    let direction = direction.normalize();
+    let yaw = direction.x.atan2(direction.z);
+    let pitch = direction.y.clamp(-1.0, 1.0).asin();
+    (yaw, pitch)
+}
+
+fn spawn_view_cube(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<GizmoMaterial>>,
+) {
+    commands.spawn((
+        Name::from("View Cube Camera"),
+        Camera {
+            order: 2,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Camera3d::default(),
+        RenderLayers::layer(VIEW_CUBE_RENDER_LAYER),
+        Transform::from_xyz(0.0, 0.0, VIEW_CUBE_CAMERA_DISTANCE).looking_at(Vec3::ZERO, Vec3::Y),
+        ViewCubeCamera,
+    ));
+
+    let face_matl = materials.add(GizmoMaterial::from(Color::srgb(0.75, 0.75, 0.78)));
+    let edge_matl = materials.add(GizmoMaterial::from(Color::srgb(0.55, 0.55, 0.58)));
+    let corner_matl = materials.add(GizmoMaterial::from(Color::srgb(0.4, 0.4, 0.43)));
+
+    let inner = VIEW_CUBE_HALF_SIZE - VIEW_CUBE_BEVEL;
+    let root = commands
+        .spawn((
+            Name::from("View Cube"),
+            Transform::default(),
+            Visibility::Visible,
+        ))
+        .id();
+
+    // Split the cube into a 3x3x3 grid of boxes, every box but the core is a handle looking at
+    // the cube from its own direction.
+    for x in -1..=1 {
+        for y in -1..=1 {
+            for z in -1..=1 {
+                let cell = IVec3::new(x, y, z);
+                if cell == IVec3::ZERO {
+                    continue;
+                }
+                let direction = cell.as_vec3();
+                let extent = direction.abs().to_array().map(|d| {
+                    if d == 0.0 {
+                        2.0 * inner
+                    } else {
+                        VIEW_CUBE_BEVEL
+                    }
+                });
+                let center = direction * (VIEW_CUBE_HALF_SIZE - VIEW_CUBE_BEVEL / 2.0);
+                let material = match cell.abs().element_sum() {
+                    1 => face_matl.clone(),
+                    2 => edge_matl.clone(),
+                    _ => corner_matl.clone(),
+                };
+                let handle = commands
+                    .spawn((
+                        Mesh3d(meshes.add(Cuboid::from_size(Vec3::from_array(extent)))),
+                        MeshMaterial3d(material),
+                        Transform::from_translation(center),
+                        ViewCubeHandle { direction },
+                        NotShadowCaster,
+                        RenderLayers::layer(VIEW_CUBE_RENDER_LAYER),
+                    ))
+                    .observe(view_cube_click)
+                    .observe(view_cube_drag)
+                    .observe(view_cube_drag_end)
+                    .id();
+                commands.entity(root).add_child(handle);
+            }
+        }
+    }
+
+    // Axis triad out of the back corner, colored like the AxisPlugin
+    let length = 2.0 * VIEW_CUBE_HALF_SIZE + 0.4;
+    let axis_mesh = meshes.add(Cylinder::new(0.03, length));
+    let origin = Vec3::splat(-VIEW_CUBE_HALF_SIZE);
+    let axes = [
+        (Vec3::X, Color::srgb(1.0, 0.0, 0.0)),
+        (Vec3::Y, Color::srgb(0.0, 1.0, 0.0)),
+        (Vec3::Z, Color::srgb(0.0, 0.0, 1.0)),
+    ];
+    for (axis, color) in axes {
+        let triad = commands
+            .spawn((
+                Mesh3d(axis_mesh.clone()),
+                MeshMaterial3d(materials.add(GizmoMaterial::from(color))),
+                Transform::from_translation(origin + axis * length / 2.0)
+                    .with_rotation(Quat::from_rotation_arc(Vec3::Y, axis)),
+                NotShadowCaster,
+                Pickable::IGNORE,
+                RenderLayers::layer(VIEW_CUBE_RENDER_LAYER),
+            ))
+            .id();
+        commands.entity(root).add_child(triad);
+    }
+}
+
+/// Keep the view cube viewport pinned to the top right corner of the window.
+fn place_view_cube_viewport(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<&mut Camera, With<ViewCubeCamera>>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok(mut camera) = cameras.single_mut() else {
+        return;
+    };
+    let scale = window.scale_factor();
+    let size = (VIEW_CUBE_VIEWPORT_SIZE * scale) as u32;
+    let margin = (VIEW_CUBE_VIEWPORT_MARGIN * scale) as u32;
+    let window_size = window.physical_size();
+    if window_size.x < size + margin || window_size.y < size + margin {
+        camera.is_active = false;
+        return;
+    }
+    let viewport = Viewport {
+        physical_position: UVec2::new(window_size.x - size - margin, margin),
+        physical_size: UVec2::splat(size),
+        ..default()
+    };
+    if camera
+        .viewport
+        .as_ref()
+        .map(|v| (v.physical_position, v.physical_size))
+        != Some((viewport.physical_position, viewport.physical_size))
+    {
+        camera.viewport = Some(viewport);
+    }
+    camera.is_active = true;
+}
+
+/// Orbit the view cube camera around the cube with the same orientation as the main camera.
+fn sync_view_cube_camera(
+    orbit_cameras: Query<&Transform, (With<PanOrbitCamera>, Without<ViewCubeCamera>)>,
+    mut cube_cameras: Query<&mut Transform, With<ViewCubeCamera>>,
+) {
+    let Ok(orbit_transform) = orbit_cameras.single() else {
+        return;
+    };
+    let Ok(mut cube_transform) = cube_cameras.single_mut() else {
+        return;
+    };
+    cube_transform.rotation = orbit_transform.rotation;
+    cube_transform.translation =
+        orbit_transform.rotation * Vec3::new(0.0, 0.0, VIEW_CUBE_CAMERA_DISTANCE);
+}
+
+fn view_cube_click(
+    mut click: On<Pointer<Click>>,
+    handles: Query<&ViewCubeHandle>,
+    drag: Res<ViewCubeDrag>,
+    mut orbit_cameras: Query<&mut PanOrbitCamera>,
+) {
+    click.propagate(false);
+    if drag.0 || click.button != PointerButton::Primary {
+        return;
+    }
+    let Ok(handle) = handles.get(click.entity) else {
+        warn!("view_cube_click couldn't find handle");
+        return;
+    };
+    let Ok(mut orbit) = orbit_cameras.single_mut() else {
+        warn!("no pan_orbit camera");
+        return;
+    };
+    let (yaw, pitch) = view_cube_yaw_pitch(handle.direction);
+    // take the short way around instead of unwinding accumulated turns
+    let turns = ((orbit.target_yaw - yaw) / std::f32::consts::TAU).round();
+    orbit.target_yaw = yaw + turns * std::f32::consts::TAU;
+    orbit.target_pitch = pitch;
+}
+
+fn view_cube_drag(
+    mut drag: On<Pointer<Drag>>,
+    mut dragging: ResMut<ViewCubeDrag>,
+    mut orbit_cameras: Query<&mut PanOrbitCamera>,
+) {
+    drag.propagate(false);
+    if drag.button != PointerButton::Primary || drag.delta == Vec2::ZERO {
+        return;
+    }
+    let Ok(mut orbit) = orbit_cameras.single_mut() else {
+        warn!("no pan_orbit camera");
+        return;
+    };
+    dragging.0 = true;
+    let limit = std::f32::consts::FRAC_PI_2;
+    orbit.target_yaw -= drag.delta.x * VIEW_CUBE_ORBIT_SENSITIVITY;
+    orbit.target_pitch =
+        (orbit.target_pitch + drag.delta.y * VIEW_CUBE_ORBIT_SENSITIVITY).clamp(-limit, limit);
+}
+
+fn view_cube_drag_end(mut drag: On<Pointer<DragEnd>>, mut dragging: ResMut<ViewCubeDrag>) {
+    drag.propagate(false);
+    dragging.0 = false;
+}